@@ -0,0 +1,126 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use zuri_nbt::decode::Reader;
+use zuri_nbt::encode::Writer;
+use zuri_nbt::encoding::{BigEndian, LittleEndian, NetworkLittleEndian};
+use zuri_nbt::{tag, NBTTag};
+
+/// Builds a compound resembling a chunk section with block entities, similar in size and shape to
+/// the data found in region files.
+fn chunk() -> NBTTag {
+    let sections: Vec<tag::Compound> = (0..24)
+        .map(|y| {
+            let palette: Vec<tag::Compound> = (0..16)
+                .map(|i| {
+                    tag::Compound::builder()
+                        .with_string("Name", format!("minecraft:block_{i}"))
+                        .with_compound(
+                            "Properties",
+                            tag::Compound::builder()
+                                .with_string("facing", "north")
+                                .with_string("waterlogged", "false"),
+                        )
+                        .build()
+                })
+                .collect();
+            tag::Compound::builder()
+                .with_byte("Y", y)
+                .with_list("palette", palette)
+                .with_long_array(
+                    "data",
+                    (0..256).map(|i| i * 0x0101_0101).collect::<Vec<_>>(),
+                )
+                .with_byte_array("BlockLight", vec![15; 2048])
+                .build()
+        })
+        .collect();
+    let block_entities: Vec<tag::Compound> = (0..32)
+        .map(|i| {
+            tag::Compound::builder()
+                .with_string("id", "minecraft:chest")
+                .with_int("x", i)
+                .with_int("y", 64)
+                .with_int("z", -i)
+                .with_list(
+                    "Items",
+                    (0..8)
+                        .map(|slot| {
+                            tag::Compound::builder()
+                                .with_byte("Slot", slot)
+                                .with_string("id", "minecraft:stone")
+                                .with_byte("Count", 64)
+                                .build()
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                .build()
+        })
+        .collect();
+    NBTTag::Compound(
+        tag::Compound::builder()
+            .with_int("DataVersion", 3465)
+            .with_int("xPos", 0)
+            .with_int("zPos", 0)
+            .with_string("Status", "minecraft:full")
+            .with_list("sections", sections)
+            .with_list("block_entities", block_entities)
+            .with_int_array("Heightmap", vec![64; 256])
+            .build(),
+    )
+}
+
+/// Builds a compound containing a single large array of each type.
+fn large_arrays() -> NBTTag {
+    NBTTag::Compound(
+        tag::Compound::builder()
+            .with_byte_array("bytes", (0..1 << 20).map(|i| i as i8).collect::<Vec<_>>())
+            .with_int_array("ints", (0..1 << 18).collect::<Vec<_>>())
+            .with_long_array("longs", (0..1 << 17).collect::<Vec<_>>())
+            .build(),
+    )
+}
+
+fn bench_encoding<T: Reader + Writer + Default>(c: &mut Criterion, encoding: &str) {
+    for (name, nbt) in [("chunk", chunk()), ("large_arrays", large_arrays())] {
+        let mut buf = vec![];
+        nbt.write(&mut buf, &T::default()).unwrap();
+
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Bytes(buf.len() as u64));
+        group.bench_function(BenchmarkId::new("read", encoding), |b| {
+            b.iter(|| NBTTag::read(&mut black_box(buf.as_slice()), &T::default()).unwrap())
+        });
+        group.bench_function(BenchmarkId::new("write", encoding), |b| {
+            let mut out = Vec::with_capacity(buf.len());
+            b.iter(|| {
+                out.clear();
+                black_box(&nbt).write(&mut out, &T::default()).unwrap();
+            })
+        });
+        group.finish();
+    }
+}
+
+/// Reads large arrays from a single heap allocated region, in the same way as reading a memory
+/// mapped file, which converts the elements of the arrays in bulk.
+fn bench_mapped(c: &mut Criterion) {
+    let mut buf = vec![];
+    large_arrays().write(&mut buf, &LittleEndian).unwrap();
+    let region: Box<[u8]> = buf.into_boxed_slice();
+
+    let mut group = c.benchmark_group("mapped_arrays");
+    group.throughput(Throughput::Bytes(region.len() as u64));
+    group.bench_function("read_from_mmap", |b| {
+        b.iter(|| NBTTag::read_from_mmap(black_box(&region), &LittleEndian).unwrap())
+    });
+    group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+    bench_mapped(c);
+    bench_encoding::<BigEndian>(c, "big_endian");
+    bench_encoding::<LittleEndian>(c, "little_endian");
+    bench_encoding::<NetworkLittleEndian>(c, "network_little_endian");
+}
+
+criterion_group!(encoding, benches);
+criterion_main!(encoding);
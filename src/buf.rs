@@ -0,0 +1,73 @@
+//! Reading and writing of NBT data using the [bytes::Buf] and [bytes::BufMut] traits.
+use bytes::{Buf, BufMut};
+
+use crate::decode::{ReadOpts, Reader};
+use crate::encode::{WriteOpts, Writer};
+use crate::{decode, encode, NBTTag};
+
+impl NBTTag {
+    /// Attempts to read the data from a [Buf] into an NBT value using the specified [Reader]
+    /// encoding. Only the bytes that make up the NBT data are consumed from the buffer.
+    pub fn read_buf(buf: &mut impl Buf, r: &impl Reader) -> decode::Res<Self> {
+        Self::read_buf_with(buf, r, &ReadOpts::default())
+    }
+
+    /// Attempts to read the data from a [Buf] into an NBT value using the specified [Reader]
+    /// encoding and [ReadOpts].
+    pub fn read_buf_with(
+        buf: &mut impl Buf,
+        r: &impl Reader,
+        opts: &ReadOpts,
+    ) -> decode::Res<Self> {
+        Self::read_with(&mut buf.reader(), r, opts)
+    }
+
+    /// Attempts to write the NBT data into a [BufMut] using the specified [Writer] encoding.
+    pub fn write_buf(&self, buf: &mut impl BufMut, w: &impl Writer) -> encode::Res {
+        self.write_buf_with(buf, w, &WriteOpts::default())
+    }
+
+    /// Attempts to write the NBT data into a [BufMut] using the specified [Writer] encoding and
+    /// [WriteOpts].
+    pub fn write_buf_with(
+        &self,
+        buf: &mut impl BufMut,
+        w: &impl Writer,
+        opts: &WriteOpts,
+    ) -> encode::Res {
+        self.write_with(&mut buf.writer(), w, opts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Buf, BufMut, BytesMut};
+
+    use crate::encoding::NetworkLittleEndian;
+    use crate::{tag, NBTTag};
+
+    #[test]
+    fn test_buf() {
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_string("name", "Zuri")
+                .with_int_array("pos", vec![0, 64, 0])
+                .build(),
+        );
+        let mut buf = BytesMut::new();
+        nbt.write_buf(&mut buf, &NetworkLittleEndian).unwrap();
+        buf.put_u8(0xff);
+
+        let mut expected = vec![];
+        nbt.write(&mut expected, &NetworkLittleEndian).unwrap();
+        assert_eq!(buf[..expected.len()], expected);
+
+        let mut bytes = buf.freeze();
+        assert_eq!(
+            NBTTag::read_buf(&mut bytes, &NetworkLittleEndian).unwrap(),
+            nbt
+        );
+        assert_eq!(bytes.remaining(), 1);
+        assert_eq!(bytes.get_u8(), 0xff);
+    }
+}
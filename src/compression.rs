@@ -0,0 +1,333 @@
+//! Reading and writing of compressed NBT data.
+use std::io::Read;
+#[cfg(any(feature = "gzip", feature = "zlib"))]
+use std::io::Write;
+
+use crate::decode::Reader;
+#[cfg(any(feature = "gzip", feature = "zlib"))]
+use crate::encode;
+#[cfg(any(feature = "gzip", feature = "zlib"))]
+use crate::encode::Writer;
+use crate::err::ErrorPath;
+#[cfg(feature = "gzip")]
+use crate::err::{ReadError, WriteError};
+use crate::{decode, NBTTag};
+
+impl NBTTag {
+    /// Attempts to read NBT data that may or may not be compressed, using the specified [Reader]
+    /// encoding for the decompressed data.
+    ///
+    /// The compression is detected by the first two bytes in the buffer: `0x1f 0x8b` indicates gzip
+    /// compressed data, `0x78` followed by one of the common zlib flag bytes indicates zlib
+    /// compressed data, and anything else is read as uncompressed data. Neither of these can be
+    /// mistaken for uncompressed NBT, as they are not valid tag types.
+    ///
+    /// Reading gzip or zlib compressed data requires the `gzip` or `zlib` feature respectively. If
+    /// the feature is not enabled, an error is returned instead.
+    pub fn read_auto(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
+        let (header, len) = read_magic(buf)?;
+        let buf = &mut header[..len].chain(buf);
+        match header[..len] {
+            [0x1f, 0x8b, ..] => {
+                #[cfg(feature = "gzip")]
+                return Self::read(&mut flate2::read::GzDecoder::new(buf), r);
+                #[cfg(not(feature = "gzip"))]
+                return Err(ErrorPath::new(crate::err::ReadError::Custom(
+                    "reading gzip compressed data requires the `gzip` feature".to_string(),
+                )));
+            }
+            [0x78, 0x01 | 0x5e | 0x9c | 0xda, ..] => {
+                #[cfg(feature = "zlib")]
+                return Self::read(&mut flate2::read::ZlibDecoder::new(buf), r);
+                #[cfg(not(feature = "zlib"))]
+                return Err(ErrorPath::new(crate::err::ReadError::Custom(
+                    "reading zlib compressed data requires the `zlib` feature".to_string(),
+                )));
+            }
+            _ => Self::read(buf, r),
+        }
+    }
+
+    /// Attempts to read gzip compressed NBT data, such as most `.dat` files of Minecraft: Java
+    /// Edition, using the specified [Reader] encoding for the decompressed data.
+    ///
+    /// If the data does not start with the gzip magic bytes `0x1f 0x8b`, a [ReadError::Custom]
+    /// error is returned. Use [Self::read_auto] to read data that may not be compressed.
+    #[cfg(feature = "gzip")]
+    pub fn read_gzip(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
+        let (header, len) = read_magic(buf)?;
+        if header[..len] != [0x1f, 0x8b] {
+            return Err(ErrorPath::new(ReadError::Custom(
+                "data is not gzip compressed".to_string(),
+            )));
+        }
+        Self::read(&mut flate2::read::GzDecoder::new(header.chain(buf)), r)
+    }
+
+    /// Attempts to write the NBT data using the specified [Writer] encoding, compressed using gzip
+    /// with the default compression level.
+    #[cfg(feature = "gzip")]
+    pub fn write_gzip(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
+        let mut encoder = flate2::write::GzEncoder::new(buf, flate2::Compression::default());
+        self.write(&mut encoder, w)?;
+        encoder.finish().map_err(|x| ErrorPath::new(x.into()))?;
+        Ok(())
+    }
+
+    /// Attempts to read zlib compressed NBT data, such as the chunks stored in the region files of
+    /// Minecraft: Java Edition, using the specified [Reader] encoding for the decompressed data.
+    ///
+    /// Data that is not zlib compressed fails to decompress, resulting in a
+    /// [crate::err::ReadError::Io] error. Use [Self::read_auto] to read data that may not be
+    /// compressed.
+    #[cfg(feature = "zlib")]
+    pub fn read_zlib(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
+        Self::read(&mut flate2::read::ZlibDecoder::new(buf), r)
+    }
+
+    /// Attempts to write the NBT data using the specified [Writer] encoding, compressed using zlib
+    /// with the default compression level.
+    #[cfg(feature = "zlib")]
+    pub fn write_zlib(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
+        self.write_zlib_with_level(buf, w, flate2::Compression::default().level())
+    }
+
+    /// Attempts to write the NBT data using the specified [Writer] encoding, compressed using zlib
+    /// with the provided compression level.
+    ///
+    /// The level ranges from 0, which does not compress the data at all, to 9, which produces the
+    /// smallest output but is the slowest. [Self::write_zlib] uses a level of 6.
+    #[cfg(feature = "zlib")]
+    pub fn write_zlib_with_level(
+        &self,
+        buf: &mut impl Write,
+        w: &impl Writer,
+        level: u32,
+    ) -> encode::Res {
+        let mut encoder = flate2::write::ZlibEncoder::new(buf, flate2::Compression::new(level));
+        self.write(&mut encoder, w)?;
+        encoder.finish().map_err(|x| ErrorPath::new(x.into()))?;
+        Ok(())
+    }
+
+    /// Returns the number of bytes the NBT data occupies when written using the specified [Writer]
+    /// encoding and compressed using gzip with the default compression level.
+    ///
+    /// The data is actually compressed to compute the size, but the compressed bytes are discarded
+    /// as they are produced rather than kept in memory.
+    #[cfg(feature = "gzip")]
+    pub fn compressed_size(&self, w: &impl Writer) -> Result<usize, ErrorPath<WriteError>> {
+        let mut encoder =
+            flate2::write::GzEncoder::new(CountingWriter(0), flate2::Compression::default());
+        self.write(&mut encoder, w)?;
+        let counter = encoder.finish().map_err(|x| ErrorPath::new(x.into()))?;
+        Ok(counter.0)
+    }
+
+    /// Reads NBT data from text containing gzip compressed data encoded as standard base64, using
+    /// the specified [Reader] encoding for the decompressed data.
+    ///
+    /// Leading and trailing whitespace in the text is ignored. Text that is not valid base64
+    /// results in a [ReadError::Custom] error.
+    #[cfg(feature = "base64")]
+    pub fn from_gzip_base64(s: &str, r: &impl Reader) -> decode::Res<Self> {
+        use base64::Engine;
+
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(s.trim())
+            .map_err(|x| ErrorPath::new(ReadError::Custom(format!("invalid base64: {x}"))))?;
+        Self::read(&mut flate2::read::GzDecoder::new(data.as_slice()), r)
+    }
+
+    /// Writes the NBT data using the specified [Writer] encoding, compresses it using gzip and
+    /// returns the compressed data encoded as standard base64. This is the inverse of
+    /// [NBTTag::from_gzip_base64].
+    #[cfg(feature = "base64")]
+    pub fn to_gzip_base64(&self, w: &impl Writer) -> Result<String, ErrorPath<WriteError>> {
+        use base64::Engine;
+
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        self.write(&mut encoder, w)?;
+        let data = encoder.finish().map_err(|x| ErrorPath::new(x.into()))?;
+        Ok(base64::engine::general_purpose::STANDARD.encode(data))
+    }
+}
+
+/// Reads the first two bytes of the buffer, which identify the compression of the data. Fewer
+/// bytes are returned if the buffer ends before that.
+fn read_magic(buf: &mut impl Read) -> decode::Res<([u8; 2], usize)> {
+    // The buffer may return fewer bytes than requested, so it cannot simply be peeked at.
+    let mut header = [0; 2];
+    let mut len = 0;
+    while len < header.len() {
+        match buf.read(&mut header[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(ErrorPath::new(err.into())),
+        }
+    }
+    Ok((header, len))
+}
+
+/// A sink that discards all data written to it, only keeping track of the number of bytes.
+#[cfg(feature = "gzip")]
+struct CountingWriter(usize);
+
+#[cfg(feature = "gzip")]
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::BigEndian;
+    use crate::{tag, NBTTag};
+
+    fn sample() -> (NBTTag, Vec<u8>) {
+        let nbt = NBTTag::Compound(tag::Compound::builder().with_int("a", 1).build());
+        let mut buf = vec![];
+        nbt.write(&mut buf, &BigEndian).unwrap();
+        (nbt, buf)
+    }
+
+    #[test]
+    fn test_read_auto_raw() {
+        let (nbt, buf) = sample();
+        assert_eq!(
+            NBTTag::read_auto(&mut buf.as_slice(), &BigEndian).unwrap(),
+            nbt
+        );
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_read_auto_gzip() {
+        use std::io::{Read, Write};
+
+        let (nbt, buf) = sample();
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(&buf).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(
+            NBTTag::read_auto(&mut compressed.as_slice(), &BigEndian).unwrap(),
+            nbt
+        );
+
+        // The magic bytes are split over two buffers.
+        let (first, rest) = compressed.split_at(1);
+        assert_eq!(
+            NBTTag::read_auto(&mut first.chain(rest), &BigEndian).unwrap(),
+            nbt
+        );
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_read_write_gzip() {
+        use crate::err::ReadError;
+
+        let (nbt, buf) = sample();
+        let mut compressed = vec![];
+        nbt.write_gzip(&mut compressed, &BigEndian).unwrap();
+        assert_eq!(compressed[..2], [0x1f, 0x8b]);
+        assert_eq!(
+            NBTTag::read_gzip(&mut compressed.as_slice(), &BigEndian).unwrap(),
+            nbt
+        );
+
+        let err = NBTTag::read_gzip(&mut buf.as_slice(), &BigEndian).unwrap_err();
+        assert!(matches!(err.inner, ReadError::Custom(_)));
+        let err = NBTTag::read_gzip(&mut [0x1f].as_slice(), &BigEndian).unwrap_err();
+        assert!(matches!(err.inner, ReadError::Custom(_)));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_compressed_size() {
+        use std::io::Write;
+
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_int_array("zeros", vec![0; 4096])
+                .build(),
+        );
+        let mut buf = vec![];
+        nbt.write(&mut buf, &BigEndian).unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(&buf).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let size = nbt.compressed_size(&BigEndian).unwrap();
+        assert_eq!(size, compressed.len());
+        assert!(size < buf.len());
+    }
+
+    #[cfg(feature = "zlib")]
+    #[test]
+    fn test_read_auto_zlib() {
+        use std::io::Write;
+
+        let (nbt, buf) = sample();
+        let mut encoder = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(&buf).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(
+            NBTTag::read_auto(&mut compressed.as_slice(), &BigEndian).unwrap(),
+            nbt
+        );
+    }
+
+    #[cfg(feature = "zlib")]
+    #[test]
+    fn test_read_write_zlib() {
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_int_array("zeros", vec![0; 4096])
+                .build(),
+        );
+        let mut compressed = vec![];
+        nbt.write_zlib(&mut compressed, &BigEndian).unwrap();
+        assert_eq!(compressed[0], 0x78);
+        assert_eq!(
+            NBTTag::read_zlib(&mut compressed.as_slice(), &BigEndian).unwrap(),
+            nbt
+        );
+
+        let mut stored = vec![];
+        nbt.write_zlib_with_level(&mut stored, &BigEndian, 0)
+            .unwrap();
+        assert!(stored.len() > compressed.len());
+        assert_eq!(
+            NBTTag::read_zlib(&mut stored.as_slice(), &BigEndian).unwrap(),
+            nbt
+        );
+
+        let (_, buf) = sample();
+        assert!(NBTTag::read_zlib(&mut buf.as_slice(), &BigEndian).is_err());
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn test_gzip_base64() {
+        use crate::err::ReadError;
+
+        let (nbt, _) = sample();
+        let text = nbt.to_gzip_base64(&BigEndian).unwrap();
+        assert!(text.starts_with("H4sI"));
+        assert_eq!(
+            NBTTag::from_gzip_base64(&format!("{text}\n"), &BigEndian).unwrap(),
+            nbt
+        );
+
+        let err = NBTTag::from_gzip_base64("not base64!", &BigEndian).unwrap_err();
+        assert!(matches!(err.inner, ReadError::Custom(_)));
+    }
+}
@@ -0,0 +1,296 @@
+//! Lightweight conversions between common Rust types and NBT data using the [IntoNbt] and
+//! [FromNbt] traits, which do not require the `serde` feature.
+//!
+//! # Usage
+//! ```
+//! # use std::collections::HashMap;
+//! # use zuri_nbt::convert::{FromNbt, IntoNbt};
+//! let scores = HashMap::from([("Zuri".to_string(), vec![10, 20])]);
+//! let nbt = scores.clone().into_nbt();
+//! assert_eq!(HashMap::<String, Vec<i32>>::from_nbt(nbt).unwrap(), scores);
+//! ```
+use std::collections::{BTreeMap, HashMap};
+
+use indexmap::IndexMap;
+
+use crate::err::{ConvertError, ErrorPath, PathPart};
+use crate::{tag, NBTTag, NBTTagType};
+
+/// A short notation for the result type used by [FromNbt].
+pub type Res<T> = Result<T, ErrorPath<ConvertError>>;
+
+/// A type that can be converted into an [NBTTag].
+///
+/// This is implemented for the integer and floating point types that have a matching tag, `bool`
+/// (as a byte tag), strings, and vectors, arrays and string-keyed maps of other [IntoNbt] types,
+/// as well as for [NBTTag] and the tag types themselves.
+pub trait IntoNbt {
+    /// Converts the value into an [NBTTag].
+    fn into_nbt(self) -> NBTTag;
+}
+
+/// A type that can be created from an [NBTTag]. This is the inverse of [IntoNbt].
+pub trait FromNbt: Sized {
+    /// Converts an [NBTTag] into a value of this type, returning a [ConvertError] with the path
+    /// of the offending tag if its type does not match.
+    fn from_nbt(tag: NBTTag) -> Res<Self>;
+}
+
+fn mismatch<T>(expected: NBTTagType, found: &NBTTag) -> Res<T> {
+    Err(ErrorPath::new(ConvertError::MismatchedType {
+        expected,
+        found: found.tag_type(),
+    }))
+}
+
+macro_rules! impl_scalar_conv {
+    ($(($typ:ty, $enum_variant:path, $tag_type:path)$(,)?)*) => {
+        $(
+            impl IntoNbt for $typ {
+                fn into_nbt(self) -> NBTTag {
+                    self.into()
+                }
+            }
+
+            impl FromNbt for $typ {
+                fn from_nbt(tag: NBTTag) -> Res<Self> {
+                    match tag {
+                        $enum_variant(v) => Ok(v.0),
+                        other => mismatch($tag_type, &other),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_scalar_conv!(
+    (i8, NBTTag::Byte, NBTTagType::Byte),
+    (i16, NBTTag::Short, NBTTagType::Short),
+    (i32, NBTTag::Int, NBTTagType::Int),
+    (i64, NBTTag::Long, NBTTagType::Long),
+    (f32, NBTTag::Float, NBTTagType::Float),
+    (f64, NBTTag::Double, NBTTagType::Double),
+);
+
+macro_rules! impl_tag_conv {
+    ($(($typ:ty, $enum_variant:path, $tag_type:path)$(,)?)*) => {
+        $(
+            impl IntoNbt for $typ {
+                fn into_nbt(self) -> NBTTag {
+                    self.into()
+                }
+            }
+
+            impl FromNbt for $typ {
+                fn from_nbt(tag: NBTTag) -> Res<Self> {
+                    match tag {
+                        $enum_variant(v) => Ok(v),
+                        other => mismatch($tag_type, &other),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_tag_conv!(
+    (tag::Byte, NBTTag::Byte, NBTTagType::Byte),
+    (tag::Short, NBTTag::Short, NBTTagType::Short),
+    (tag::Int, NBTTag::Int, NBTTagType::Int),
+    (tag::Long, NBTTag::Long, NBTTagType::Long),
+    (tag::Float, NBTTag::Float, NBTTagType::Float),
+    (tag::Double, NBTTag::Double, NBTTagType::Double),
+    (tag::String, NBTTag::String, NBTTagType::String),
+    (tag::Compound, NBTTag::Compound, NBTTagType::Compound),
+    (tag::List, NBTTag::List, NBTTagType::List),
+    (tag::ByteArray, NBTTag::ByteArray, NBTTagType::ByteArray),
+    (tag::IntArray, NBTTag::IntArray, NBTTagType::IntArray),
+    (tag::LongArray, NBTTag::LongArray, NBTTagType::LongArray),
+);
+
+impl IntoNbt for NBTTag {
+    fn into_nbt(self) -> NBTTag {
+        self
+    }
+}
+
+impl FromNbt for NBTTag {
+    fn from_nbt(tag: NBTTag) -> Res<Self> {
+        Ok(tag)
+    }
+}
+
+/// Booleans are stored as a byte tag that is either 1 or 0.
+impl IntoNbt for bool {
+    fn into_nbt(self) -> NBTTag {
+        NBTTag::Byte(tag::Byte(self as i8))
+    }
+}
+
+/// Any byte tag other than 0 is read as `true`.
+impl FromNbt for bool {
+    fn from_nbt(tag: NBTTag) -> Res<Self> {
+        match tag {
+            NBTTag::Byte(v) => Ok(v.0 != 0),
+            other => mismatch(NBTTagType::Byte, &other),
+        }
+    }
+}
+
+impl IntoNbt for String {
+    fn into_nbt(self) -> NBTTag {
+        self.into()
+    }
+}
+
+impl IntoNbt for &str {
+    fn into_nbt(self) -> NBTTag {
+        self.into()
+    }
+}
+
+/// String tags that are not valid UTF-8 are converted lossily, replacing the invalid bytes with
+/// the replacement character.
+impl FromNbt for String {
+    fn from_nbt(tag: NBTTag) -> Res<Self> {
+        match tag {
+            NBTTag::String(tag::String::Utf8(v)) => Ok(v),
+            NBTTag::String(tag::String::Bytes(v)) => Ok(String::from_utf8_lossy(&v).into_owned()),
+            other => mismatch(NBTTagType::String, &other),
+        }
+    }
+}
+
+/// Vectors are stored as a list tag, even if an array tag exists for the element type. Use the
+/// array tag types directly to convert to and from array tags.
+impl<T: IntoNbt> IntoNbt for Vec<T> {
+    fn into_nbt(self) -> NBTTag {
+        NBTTag::List(tag::List(self.into_iter().map(T::into_nbt).collect(), None))
+    }
+}
+
+impl<T: FromNbt> FromNbt for Vec<T> {
+    fn from_nbt(tag: NBTTag) -> Res<Self> {
+        match tag {
+            NBTTag::List(v) => Vec::from(v)
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| T::from_nbt(v).map_err(|err| err.prepend(PathPart::Element(i))))
+                .collect(),
+            other => mismatch(NBTTagType::List, &other),
+        }
+    }
+}
+
+impl<T: IntoNbt, const N: usize> IntoNbt for [T; N] {
+    fn into_nbt(self) -> NBTTag {
+        NBTTag::List(tag::List(self.into_iter().map(T::into_nbt).collect(), None))
+    }
+}
+
+impl<T: FromNbt, const N: usize> FromNbt for [T; N] {
+    fn from_nbt(tag: NBTTag) -> Res<Self> {
+        Vec::<T>::from_nbt(tag)?.try_into().map_err(|v: Vec<T>| {
+            ErrorPath::new(ConvertError::LengthMismatch {
+                expected: N,
+                found: v.len(),
+            })
+        })
+    }
+}
+
+macro_rules! impl_map_conv {
+    ($($map:ident$(,)?)*) => {
+        $(
+            impl<T: IntoNbt> IntoNbt for $map<String, T> {
+                fn into_nbt(self) -> NBTTag {
+                    NBTTag::Compound(tag::Compound(
+                        self.into_iter().map(|(k, v)| (k, v.into_nbt())).collect(),
+                    ))
+                }
+            }
+
+            impl<T: FromNbt> FromNbt for $map<String, T> {
+                fn from_nbt(tag: NBTTag) -> Res<Self> {
+                    match tag {
+                        NBTTag::Compound(v) => v
+                            .0
+                            .into_iter()
+                            .map(|(k, v)| match T::from_nbt(v) {
+                                Ok(v) => Ok((k, v)),
+                                Err(err) => Err(err.prepend(PathPart::MapKey(k))),
+                            })
+                            .collect(),
+                        other => mismatch(NBTTagType::Compound, &other),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_map_conv!(HashMap, BTreeMap, IndexMap);
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::convert::{FromNbt, IntoNbt};
+    use crate::err::{ConvertError, PathPart};
+    use crate::{tag, NBTTag, NBTTagType};
+
+    #[test]
+    fn test_convert() {
+        let value = BTreeMap::from([
+            ("a".to_string(), vec![[1i16, 2], [3, 4]]),
+            ("b".to_string(), vec![]),
+        ]);
+        let nbt = value.clone().into_nbt();
+        assert_eq!(
+            nbt,
+            NBTTag::Compound(tag::Compound::from([
+                (
+                    "a",
+                    tag::List::from([tag::List::from([1i16, 2]), tag::List::from([3i16, 4])])
+                ),
+                ("b", tag::List::default()),
+            ]))
+        );
+        assert_eq!(BTreeMap::from_nbt(nbt).unwrap(), value);
+
+        assert_eq!(true.into_nbt(), NBTTag::Byte(tag::Byte(1)));
+        assert!(bool::from_nbt(NBTTag::Byte(tag::Byte(2))).unwrap());
+        assert_eq!(
+            String::from_nbt("text".into_nbt()).unwrap(),
+            "text".to_string()
+        );
+    }
+
+    #[test]
+    fn test_convert_error() {
+        let nbt = BTreeMap::from([("a".to_string(), vec![1i32, 2])]).into_nbt();
+        let err = BTreeMap::<String, Vec<i64>>::from_nbt(nbt.clone()).unwrap_err();
+        assert_eq!(
+            err.inner,
+            ConvertError::MismatchedType {
+                expected: NBTTagType::Long,
+                found: NBTTagType::Int,
+            }
+        );
+        assert_eq!(
+            Vec::from(err.path.0),
+            vec![PathPart::MapKey("a".to_string()), PathPart::Element(0)]
+        );
+
+        let err = BTreeMap::<String, [i32; 3]>::from_nbt(nbt).unwrap_err();
+        assert_eq!(
+            err.inner,
+            ConvertError::LengthMismatch {
+                expected: 3,
+                found: 2
+            }
+        );
+        assert_eq!(err.path.to_string(), "a");
+    }
+}
@@ -1,13 +1,314 @@
 //! See [Reader].
-use crate::err::{ErrorPath, PathPart, ReadError};
+use crate::err::{ErrorPath, Path, PathPart, ReadError};
+use crate::tag::ids;
+use std::collections::HashMap;
+use std::io;
 use std::io::Read;
 
 /// A short notation for the result type used in the [Reader].
 pub type Res<T> = Result<T, ErrorPath<ReadError>>;
 
+/// The maximum nesting depth of compound and list tags that is used by default, which is the
+/// same limit that Minecraft uses. See [ReadOpts::max_depth].
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+/// Options that alter how NBT data is read using [crate::NBTTag::read_with].
+#[derive(Debug, Clone)]
+pub struct ReadOpts {
+    /// The maximum depth to which compound and list tags may be nested, where a compound or list
+    /// tag at the root has a depth of 1. No limit is applied if this is `None`.
+    ///
+    /// Defaults to [DEFAULT_MAX_DEPTH]. Nested tags are read iteratively, but writing, comparing,
+    /// cloning and dropping them is recursive, so removing the limit allows untrusted data to
+    /// overflow the stack later on.
+    pub max_depth: Option<usize>,
+    /// Determines what happens when a compound or list tag exceeds the [Self::max_depth].
+    pub depth_policy: DepthPolicy,
+    /// Tag ids that are not known to this crate, mapped to the size in bytes of a single element,
+    /// that should be read as length-prefixed arrays rather than failing with a
+    /// [ReadError::UnknownTagType] error.
+    ///
+    /// Such tags are read into an [crate::NBTTag::Unknown] tag, which keeps the payload as raw
+    /// bytes so that it can be written again without losing any data. The payload of an unknown
+    /// tag cannot be interpreted without knowing its layout, which is why only array-like tags
+    /// with a declared element size are supported. Unknown tag ids that are not present here are
+    /// still rejected.
+    pub unknown_arrays: HashMap<u8, usize>,
+    /// The maximum total number of elements that may be read across the entire value, counting
+    /// every element of list and array tags and every entry of compound tags. No limit is applied
+    /// if this is `None`.
+    ///
+    /// Unlike a limit on the length of a single list, this cannot be circumvented by spreading the
+    /// elements over many smaller containers. The length prefix of a list or array is checked
+    /// against the remaining budget before any of its elements are read.
+    pub max_elements: Option<usize>,
+    /// The maximum total number of bytes that may be read from the buffer for the entire value,
+    /// including the header of the root tag. No limit is applied if this is `None`, which is the
+    /// default.
+    ///
+    /// This bounds both the time spent reading and the memory used by the read value, regardless
+    /// of the length prefixes in the data, which makes it suitable for reading data received from
+    /// untrusted sources. Reading fails with a [ReadError::ByteLimitExceeded] error as soon as the
+    /// budget runs out.
+    pub max_bytes: Option<usize>,
+    /// The maximum length in bytes of the encoded form of any string, including compound keys. No
+    /// limit is applied if this is `None`.
+    ///
+    /// The length prefix is checked before any of the string is read, and a string that is too
+    /// long results in a [ReadError::SeqLengthViolation] error.
+    pub max_string_len: Option<usize>,
+    /// Whether float and double tags that are NaN are read as [f32::NAN] and [f64::NAN]
+    /// respectively, rather than keeping the exact bits from the buffer.
+    ///
+    /// NaN values can have many different bit patterns, so this makes the read data, and anything
+    /// that is computed from it such as a hash of the written data, independent of which NaN was
+    /// stored.
+    pub canonicalize_nan: bool,
+}
+
+impl Default for ReadOpts {
+    fn default() -> Self {
+        Self {
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            depth_policy: DepthPolicy::default(),
+            unknown_arrays: HashMap::new(),
+            max_elements: None,
+            max_bytes: None,
+            max_string_len: None,
+            canonicalize_nan: false,
+        }
+    }
+}
+
+/// Determines what happens when the data being read exceeds the maximum nesting depth.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum DepthPolicy {
+    /// Fail reading with a [ReadError::DepthLimitExceeded] error.
+    #[default]
+    Error,
+    /// Skip the payload of any compound or list tag that exceeds the maximum depth, and replace it
+    /// with an empty tag of the same type. This produces a best-effort tree, which is mostly useful
+    /// for displaying untrusted data.
+    Truncate,
+}
+
+/// A recoverable problem that was found while reading NBT data using
+/// [crate::NBTTag::read_with_warnings].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Warning {
+    /// The path of the tag at which the problem was found.
+    pub path: Path,
+    /// What the problem is.
+    pub reason: WarningReason,
+}
+
+/// Describes a recoverable problem that was found while reading NBT data.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum WarningReason {
+    /// A string tag is not valid UTF-8, and was read as a [crate::tag::String::Bytes] string.
+    InvalidString,
+    /// A compound contains the same key more than once. The value read last is kept.
+    DuplicateKey,
+    /// An empty list declares an element type that is not a known tag type. Contains the declared
+    /// id. The list is read as an empty list.
+    UnknownEmptyListType(u8),
+}
+
+/// Keeps track of the state while reading a single NBT value.
+pub(crate) struct ReadCtx<'a> {
+    opts: &'a ReadOpts,
+    depth: usize,
+    /// The number of elements that may still be read, if [ReadOpts::max_elements] is set.
+    remaining_elements: Option<usize>,
+    /// The warnings found so far, if warnings are being collected.
+    warnings: Option<Vec<Warning>>,
+    /// The path of the tag currently being read. Only kept up to date if warnings are being
+    /// collected.
+    path: Path,
+}
+
+impl<'a> ReadCtx<'a> {
+    /// Creates a new context for reading a value at the root.
+    pub(crate) fn new(opts: &'a ReadOpts) -> Self {
+        Self {
+            opts,
+            depth: 0,
+            remaining_elements: opts.max_elements,
+            warnings: None,
+            path: Path::default(),
+        }
+    }
+
+    /// Creates a new context for reading a value at the root that collects any [Warning]s.
+    pub(crate) fn with_warnings(opts: &'a ReadOpts) -> Self {
+        Self {
+            warnings: Some(Vec::new()),
+            ..Self::new(opts)
+        }
+    }
+
+    /// Returns whether warnings are being collected.
+    pub(crate) fn collects_warnings(&self) -> bool {
+        self.warnings.is_some()
+    }
+
+    /// Records a warning for the tag currently being read, if warnings are being collected.
+    pub(crate) fn warn(&mut self, reason: WarningReason) {
+        if let Some(warnings) = &mut self.warnings {
+            warnings.push(Warning {
+                path: self.path.clone(),
+                reason,
+            });
+        }
+    }
+
+    /// Descends into a child of the tag currently being read. The part is only created if
+    /// warnings are being collected, after which [Self::pop_path] must be called.
+    pub(crate) fn push_path(&mut self, part: impl FnOnce() -> PathPart) {
+        if self.collects_warnings() {
+            self.path.push_back(part());
+        }
+    }
+
+    /// Returns to the parent of the tag currently being read.
+    pub(crate) fn pop_path(&mut self) {
+        self.path.0.pop_back();
+    }
+
+    /// Consumes the context, returning the warnings that were collected.
+    pub(crate) fn into_warnings(self) -> Vec<Warning> {
+        self.warnings.unwrap_or_default()
+    }
+
+    /// Returns whether an element budget is being enforced.
+    pub(crate) fn has_element_budget(&self) -> bool {
+        self.remaining_elements.is_some()
+    }
+
+    /// Deducts `n` elements that are about to be read from the element budget, failing if the
+    /// budget is exceeded.
+    pub(crate) fn take_elements(&mut self, n: usize) -> Res<()> {
+        if let Some(remaining) = &mut self.remaining_elements {
+            *remaining = remaining.checked_sub(n).ok_or_else(|| {
+                ErrorPath::new(ReadError::ElementLimitExceeded(
+                    self.opts.max_elements.unwrap_or_default(),
+                ))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Enters a nested compound or list tag. Returns `false` if the tag exceeds the maximum depth
+    /// and should be truncated, after which [Self::exit] must still be called.
+    pub(crate) fn enter(&mut self) -> Res<bool> {
+        self.depth += 1;
+        match self.opts.max_depth {
+            Some(max) if self.depth > max => match self.opts.depth_policy {
+                DepthPolicy::Error => Err(ErrorPath::new(ReadError::DepthLimitExceeded(max))),
+                DepthPolicy::Truncate => Ok(false),
+            },
+            _ => Ok(true),
+        }
+    }
+
+    /// Returns the options that the value is being read with.
+    pub(crate) fn opts(&self) -> &'a ReadOpts {
+        self.opts
+    }
+
+    /// Exits a compound or list tag previously entered with [Self::enter].
+    pub(crate) fn exit(&mut self) {
+        self.depth -= 1;
+    }
+}
+
+/// Wraps a buffer to enforce the byte budget set by [ReadOpts::max_bytes].
+pub(crate) struct LimitedRead<R> {
+    inner: R,
+    /// The number of bytes that may still be read, if a budget is being enforced.
+    remaining: Option<usize>,
+    /// Whether a read has failed because the budget ran out.
+    exceeded: bool,
+}
+
+impl<R: Read> LimitedRead<R> {
+    /// Wraps the buffer, allowing at most `max_bytes` bytes to be read from it.
+    pub(crate) fn new(inner: R, max_bytes: Option<usize>) -> Self {
+        Self {
+            inner,
+            remaining: max_bytes,
+            exceeded: false,
+        }
+    }
+
+    /// Replaces the error of a result produced while reading from this buffer with a
+    /// [ReadError::ByteLimitExceeded] error if it was caused by the budget running out.
+    pub(crate) fn finish<T>(&self, res: Res<T>, max_bytes: Option<usize>) -> Res<T> {
+        match res {
+            Err(err) if self.exceeded => Err(ErrorPath::new_with_path(
+                ReadError::ByteLimitExceeded(max_bytes.unwrap_or_default()),
+                err.path,
+            )),
+            res => res,
+        }
+    }
+
+    /// Deducts `n` bytes that are about to be read from the budget, failing if the budget is
+    /// exceeded.
+    fn take(&mut self, n: usize) -> io::Result<()> {
+        if let Some(remaining) = &mut self.remaining {
+            if n > *remaining {
+                self.exceeded = true;
+                return Err(io::Error::other("byte limit exceeded"));
+            }
+            *remaining -= n;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for LimitedRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Only fail when nothing at all can be read, so that short reads behave as usual.
+        let len = match self.remaining {
+            Some(0) if !buf.is_empty() => return self.take(1).map(|_| 0),
+            Some(remaining) => buf.len().min(remaining),
+            None => buf.len(),
+        };
+        let read = self.inner.read(&mut buf[..len])?;
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= read;
+        }
+        Ok(read)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.take(buf.len())?;
+        self.inner.read_exact(buf)
+    }
+}
+
+/// The maximum number of bytes that are allocated up front for a sequence based on its length
+/// prefix. Longer sequences grow as their elements are actually read, so that a malicious length
+/// prefix cannot cause a huge allocation.
+const MAX_PREALLOC_BYTES: usize = 1 << 20;
+
+/// Returns the capacity to allocate up front for a sequence of `len` elements of type `T`.
+///
+/// Uses checked arithmetic, so the computed size can never overflow on targets with a small
+/// `usize`.
+pub(crate) fn prealloc_capacity<T>(len: usize) -> usize {
+    match len.checked_mul(std::mem::size_of::<T>()) {
+        Some(size) if size <= MAX_PREALLOC_BYTES => len,
+        _ => MAX_PREALLOC_BYTES / std::mem::size_of::<T>().max(1),
+    }
+}
+
 /// A trait that can be implemented to alter how basic NBT types are read.
 ///
-/// All the implemented methods must not panic.
+/// All the implemented methods must not panic. They must also not assume that a single call to
+/// [Read::read] returns all requested bytes, as buffers such as network streams may return fewer,
+/// so multi-byte values should be read using [Read::read_exact] or equivalent.
 pub trait Reader {
     /// Reads an 8-bit unsigned integer.
     fn u8(&self, buf: &mut impl Read) -> Res<u8>;
@@ -24,17 +325,40 @@ pub trait Reader {
     /// Reads a 64-bit floating point number.
     fn f64(&self, buf: &mut impl Read) -> Res<f64>;
 
+    /// Reads exactly `len` raw bytes into an owned buffer, for example to decode the bytes later
+    /// on a different thread.
+    ///
+    /// Returns a [ReadError::Io] error if the buffer ends before `len` bytes were read. Memory is
+    /// only allocated as bytes are actually read, so an untrusted `len` is safe to pass.
+    fn bytes(&self, buf: &mut impl Read, len: usize) -> Res<Vec<u8>> {
+        let mut vec_buf = Vec::new();
+        buf.take(len as u64)
+            .read_to_end(&mut vec_buf)
+            .map_err(|x| ErrorPath::new(x.into()))?;
+        if vec_buf.len() != len {
+            return Err(ErrorPath::new(ReadError::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("expected {len} bytes, but only {} were read", vec_buf.len()),
+            ))));
+        }
+        Ok(vec_buf)
+    }
+
     /// Reads the NBT `end` tag, which indicates the end of a compound tag.
     fn end(&self, buf: &mut impl Read) -> Res<()> {
         let t = self.u8(buf)?;
-        if t != 0 {
-            return Err(ErrorPath::new(ReadError::UnexpectedTag(0, t)));
+        if t != ids::END {
+            return Err(ErrorPath::new(ReadError::UnexpectedTag(ids::END, t)));
         }
         Ok(())
     }
 
-    /// Reads a variable-length string.
-    fn string(&self, buf: &mut impl Read) -> Res<String> {
+    /// Reads the length prefix of a variable-length string, which is the length in bytes of its
+    /// encoded form.
+    ///
+    /// Implementations that override [Self::string] with a different length prefix should also
+    /// override this method, as it is used on its own when [ReadOpts::max_string_len] is set.
+    fn string_len(&self, buf: &mut impl Read) -> Res<usize> {
         let len = self.i16(buf)?;
         if len < 0 {
             return Err(ErrorPath::new(ReadError::SeqLengthViolation(
@@ -42,18 +366,21 @@ pub trait Reader {
                 len as usize,
             )));
         }
+        Ok(len as usize)
+    }
 
-        let mut str_buf = Vec::with_capacity(len as usize);
-        for i in 0..len {
-            str_buf.push(
-                self.u8(buf)
-                    .map_err(|err| err.prepend(PathPart::Element(i as usize)))?,
-            );
-        }
-        match cesu8::from_java_cesu8(&str_buf) {
-            Ok(str) => Ok(str.into_owned()),
-            Err(_) => Err(ErrorPath::new(ReadError::InvalidString(str_buf))),
-        }
+    /// Reads a variable-length string.
+    fn string(&self, buf: &mut impl Read) -> Res<String> {
+        let len = self.string_len(buf)?;
+        string_payload(self, buf, len)
+    }
+
+    /// Reads the name of the root tag, which directly follows the type of the root tag.
+    ///
+    /// Encodings that do not name the root tag should override this method to return an empty
+    /// string without reading anything.
+    fn root_name(&self, buf: &mut impl Read) -> Res<String> {
+        self.string(buf)
     }
 
     /// Reads variable-length array of 8-bit unsigned integers.
@@ -66,7 +393,7 @@ pub trait Reader {
             )));
         }
 
-        let mut vec_buf = Vec::with_capacity(len as usize);
+        let mut vec_buf = Vec::with_capacity(prealloc_capacity::<u8>(len as usize));
         for i in 0..len {
             vec_buf.push(
                 self.u8(buf)
@@ -87,7 +414,7 @@ pub trait Reader {
             )));
         }
 
-        let mut vec_buf = Vec::with_capacity(len as usize);
+        let mut vec_buf = Vec::with_capacity(prealloc_capacity::<i8>(len as usize));
         for i in 0..len {
             vec_buf.push(
                 self.i8(buf)
@@ -108,7 +435,7 @@ pub trait Reader {
             )));
         }
 
-        let mut vec_buf = Vec::with_capacity(len as usize);
+        let mut vec_buf = Vec::with_capacity(prealloc_capacity::<i32>(len as usize));
         for i in 0..len {
             vec_buf.push(
                 self.i32(buf)
@@ -129,7 +456,7 @@ pub trait Reader {
             )));
         }
 
-        let mut vec_buf = Vec::with_capacity(len as usize);
+        let mut vec_buf = Vec::with_capacity(prealloc_capacity::<i64>(len as usize));
         for i in 0..len {
             vec_buf.push(
                 self.i64(buf)
@@ -140,3 +467,264 @@ pub trait Reader {
         Ok(vec_buf)
     }
 }
+
+/// Reads the `len` bytes of an encoded string following its length prefix, and decodes them.
+pub(crate) fn string_payload<R: Reader + ?Sized>(
+    r: &R,
+    buf: &mut impl Read,
+    len: usize,
+) -> Res<String> {
+    let mut str_buf = Vec::with_capacity(prealloc_capacity::<u8>(len));
+    for i in 0..len {
+        str_buf.push(r.u8(buf).map_err(|err| err.prepend(PathPart::Element(i)))?);
+    }
+    match cesu8::from_java_cesu8(&str_buf) {
+        Ok(str) => Ok(str.into_owned()),
+        Err(_) => Err(ErrorPath::new(ReadError::InvalidString(str_buf))),
+    }
+}
+
+/// An object-safe version of the [Reader] trait, which allows the encoding to be chosen at runtime
+/// using trait objects such as `Box<dyn DynReader>`.
+///
+/// This trait is implemented for every [Reader], and [Reader] is in turn implemented for
+/// `dyn DynReader`, as well as for references and boxes of it. This means a trait object can be
+/// passed anywhere a [Reader] is expected.
+pub trait DynReader {
+    /// Reads an 8-bit unsigned integer.
+    fn dyn_u8(&self, buf: &mut dyn Read) -> Res<u8>;
+    /// Reads an 8-bit signed integer.
+    fn dyn_i8(&self, buf: &mut dyn Read) -> Res<i8>;
+    /// Reads a 16-bit signed integer.
+    fn dyn_i16(&self, buf: &mut dyn Read) -> Res<i16>;
+    /// Reads a 32-bit signed integer.
+    fn dyn_i32(&self, buf: &mut dyn Read) -> Res<i32>;
+    /// Reads a 64-bit signed integer.
+    fn dyn_i64(&self, buf: &mut dyn Read) -> Res<i64>;
+    /// Reads a 32-bit floating point number.
+    fn dyn_f32(&self, buf: &mut dyn Read) -> Res<f32>;
+    /// Reads a 64-bit floating point number.
+    fn dyn_f64(&self, buf: &mut dyn Read) -> Res<f64>;
+    /// Reads exactly `len` raw bytes. See [Reader::bytes].
+    fn dyn_bytes(&self, buf: &mut dyn Read, len: usize) -> Res<Vec<u8>>;
+    /// Reads the NBT `end` tag.
+    fn dyn_end(&self, buf: &mut dyn Read) -> Res<()>;
+    /// Reads the length prefix of a variable-length string. See [Reader::string_len].
+    fn dyn_string_len(&self, buf: &mut dyn Read) -> Res<usize>;
+    /// Reads a variable-length string.
+    fn dyn_string(&self, buf: &mut dyn Read) -> Res<String>;
+    /// Reads the name of the root tag.
+    fn dyn_root_name(&self, buf: &mut dyn Read) -> Res<String>;
+    /// Reads variable-length array of 8-bit unsigned integers.
+    fn dyn_u8_vec(&self, buf: &mut dyn Read) -> Res<Vec<u8>>;
+    /// Reads variable-length array of 8-bit signed integers.
+    fn dyn_i8_vec(&self, buf: &mut dyn Read) -> Res<Vec<i8>>;
+    /// Reads variable-length array of 32-bit signed integers.
+    fn dyn_i32_vec(&self, buf: &mut dyn Read) -> Res<Vec<i32>>;
+    /// Reads variable-length array of 64-bit signed integers.
+    fn dyn_i64_vec(&self, buf: &mut dyn Read) -> Res<Vec<i64>>;
+}
+
+impl<T: Reader> DynReader for T {
+    fn dyn_u8(&self, mut buf: &mut dyn Read) -> Res<u8> {
+        self.u8(&mut buf)
+    }
+
+    fn dyn_i8(&self, mut buf: &mut dyn Read) -> Res<i8> {
+        self.i8(&mut buf)
+    }
+
+    fn dyn_i16(&self, mut buf: &mut dyn Read) -> Res<i16> {
+        self.i16(&mut buf)
+    }
+
+    fn dyn_i32(&self, mut buf: &mut dyn Read) -> Res<i32> {
+        self.i32(&mut buf)
+    }
+
+    fn dyn_i64(&self, mut buf: &mut dyn Read) -> Res<i64> {
+        self.i64(&mut buf)
+    }
+
+    fn dyn_f32(&self, mut buf: &mut dyn Read) -> Res<f32> {
+        self.f32(&mut buf)
+    }
+
+    fn dyn_f64(&self, mut buf: &mut dyn Read) -> Res<f64> {
+        self.f64(&mut buf)
+    }
+
+    fn dyn_bytes(&self, mut buf: &mut dyn Read, len: usize) -> Res<Vec<u8>> {
+        self.bytes(&mut buf, len)
+    }
+
+    fn dyn_end(&self, mut buf: &mut dyn Read) -> Res<()> {
+        self.end(&mut buf)
+    }
+
+    fn dyn_string_len(&self, mut buf: &mut dyn Read) -> Res<usize> {
+        self.string_len(&mut buf)
+    }
+
+    fn dyn_string(&self, mut buf: &mut dyn Read) -> Res<String> {
+        self.string(&mut buf)
+    }
+
+    fn dyn_root_name(&self, mut buf: &mut dyn Read) -> Res<String> {
+        self.root_name(&mut buf)
+    }
+
+    fn dyn_u8_vec(&self, mut buf: &mut dyn Read) -> Res<Vec<u8>> {
+        self.u8_vec(&mut buf)
+    }
+
+    fn dyn_i8_vec(&self, mut buf: &mut dyn Read) -> Res<Vec<i8>> {
+        self.i8_vec(&mut buf)
+    }
+
+    fn dyn_i32_vec(&self, mut buf: &mut dyn Read) -> Res<Vec<i32>> {
+        self.i32_vec(&mut buf)
+    }
+
+    fn dyn_i64_vec(&self, mut buf: &mut dyn Read) -> Res<Vec<i64>> {
+        self.i64_vec(&mut buf)
+    }
+}
+
+impl Reader for dyn DynReader + '_ {
+    fn u8(&self, buf: &mut impl Read) -> Res<u8> {
+        self.dyn_u8(buf)
+    }
+
+    fn i8(&self, buf: &mut impl Read) -> Res<i8> {
+        self.dyn_i8(buf)
+    }
+
+    fn i16(&self, buf: &mut impl Read) -> Res<i16> {
+        self.dyn_i16(buf)
+    }
+
+    fn i32(&self, buf: &mut impl Read) -> Res<i32> {
+        self.dyn_i32(buf)
+    }
+
+    fn i64(&self, buf: &mut impl Read) -> Res<i64> {
+        self.dyn_i64(buf)
+    }
+
+    fn f32(&self, buf: &mut impl Read) -> Res<f32> {
+        self.dyn_f32(buf)
+    }
+
+    fn f64(&self, buf: &mut impl Read) -> Res<f64> {
+        self.dyn_f64(buf)
+    }
+
+    fn bytes(&self, buf: &mut impl Read, len: usize) -> Res<Vec<u8>> {
+        self.dyn_bytes(buf, len)
+    }
+
+    fn end(&self, buf: &mut impl Read) -> Res<()> {
+        self.dyn_end(buf)
+    }
+
+    fn string_len(&self, buf: &mut impl Read) -> Res<usize> {
+        self.dyn_string_len(buf)
+    }
+
+    fn string(&self, buf: &mut impl Read) -> Res<String> {
+        self.dyn_string(buf)
+    }
+
+    fn root_name(&self, buf: &mut impl Read) -> Res<String> {
+        self.dyn_root_name(buf)
+    }
+
+    fn u8_vec(&self, buf: &mut impl Read) -> Res<Vec<u8>> {
+        self.dyn_u8_vec(buf)
+    }
+
+    fn i8_vec(&self, buf: &mut impl Read) -> Res<Vec<i8>> {
+        self.dyn_i8_vec(buf)
+    }
+
+    fn i32_vec(&self, buf: &mut impl Read) -> Res<Vec<i32>> {
+        self.dyn_i32_vec(buf)
+    }
+
+    fn i64_vec(&self, buf: &mut impl Read) -> Res<Vec<i64>> {
+        self.dyn_i64_vec(buf)
+    }
+}
+
+macro_rules! impl_deref_reader {
+    ($($typ:ty$(,)?)*) => {
+        $(impl<R: Reader + ?Sized> Reader for $typ {
+            fn u8(&self, buf: &mut impl Read) -> Res<u8> {
+                (**self).u8(buf)
+            }
+
+            fn i8(&self, buf: &mut impl Read) -> Res<i8> {
+                (**self).i8(buf)
+            }
+
+            fn i16(&self, buf: &mut impl Read) -> Res<i16> {
+                (**self).i16(buf)
+            }
+
+            fn i32(&self, buf: &mut impl Read) -> Res<i32> {
+                (**self).i32(buf)
+            }
+
+            fn i64(&self, buf: &mut impl Read) -> Res<i64> {
+                (**self).i64(buf)
+            }
+
+            fn f32(&self, buf: &mut impl Read) -> Res<f32> {
+                (**self).f32(buf)
+            }
+
+            fn f64(&self, buf: &mut impl Read) -> Res<f64> {
+                (**self).f64(buf)
+            }
+
+            fn bytes(&self, buf: &mut impl Read, len: usize) -> Res<Vec<u8>> {
+                (**self).bytes(buf, len)
+            }
+
+            fn end(&self, buf: &mut impl Read) -> Res<()> {
+                (**self).end(buf)
+            }
+
+            fn string_len(&self, buf: &mut impl Read) -> Res<usize> {
+                (**self).string_len(buf)
+            }
+
+            fn string(&self, buf: &mut impl Read) -> Res<String> {
+                (**self).string(buf)
+            }
+
+            fn root_name(&self, buf: &mut impl Read) -> Res<String> {
+                (**self).root_name(buf)
+            }
+
+            fn u8_vec(&self, buf: &mut impl Read) -> Res<Vec<u8>> {
+                (**self).u8_vec(buf)
+            }
+
+            fn i8_vec(&self, buf: &mut impl Read) -> Res<Vec<i8>> {
+                (**self).i8_vec(buf)
+            }
+
+            fn i32_vec(&self, buf: &mut impl Read) -> Res<Vec<i32>> {
+                (**self).i32_vec(buf)
+            }
+
+            fn i64_vec(&self, buf: &mut impl Read) -> Res<Vec<i64>> {
+                (**self).i64_vec(buf)
+            }
+        })*
+    };
+}
+
+impl_deref_reader!(&R, Box<R>);
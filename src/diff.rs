@@ -0,0 +1,260 @@
+//! Support for computing the differences between NBT data, and for applying those differences to
+//! other data later on. See [NBTTag::diff] and [NBTTag::apply_patch].
+use crate::err::{ErrorPath, PatchError, Path, PathPart};
+use crate::NBTTag;
+
+/// A single difference between two NBT tags, as returned by [NBTTag::diff].
+///
+/// Differences only contain the new values, so that a patch stays compact and can be applied to
+/// data that is similar, but not identical, to the data it was computed from. The path of a
+/// difference can be stored using its textual form, which can be parsed back into a [Path].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    /// An entry was added to a compound tag.
+    Added {
+        /// The path of the added entry.
+        path: Path,
+        /// The value of the added entry.
+        value: NBTTag,
+    },
+    /// An entry was removed from a compound tag.
+    Removed {
+        /// The path of the removed entry.
+        path: Path,
+    },
+    /// A tag was replaced with a different value.
+    Changed {
+        /// The path of the replaced tag.
+        path: Path,
+        /// The new value of the tag.
+        value: NBTTag,
+    },
+}
+
+impl Difference {
+    /// Returns the path of the tag that the difference applies to.
+    pub fn path(&self) -> &Path {
+        match self {
+            Difference::Added { path, .. } => path,
+            Difference::Removed { path } => path,
+            Difference::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl NBTTag {
+    /// Computes the differences that turn this tag into `other`, such that applying them using
+    /// [Self::apply_patch] results in a tag equal to `other`.
+    ///
+    /// Compound tags are compared entry by entry, and lists of the same length are compared
+    /// element by element. Any other tags that differ, including lists of different lengths, are
+    /// reported as a single [Difference::Changed]. Float and double tags are compared by their
+    /// bits, so NaN values are only reported if their bits differ.
+    pub fn diff(&self, other: &NBTTag) -> Vec<Difference> {
+        let mut differences = Vec::new();
+        diff_into(self, other, &Path::default(), &mut differences);
+        differences
+    }
+
+    /// Applies differences returned by [Self::diff] to this tag, in order.
+    ///
+    /// A difference cannot be applied if the tag it changes or removes no longer exists, if the
+    /// entry it adds already exists, or if its path does not match the structure of the tag. In
+    /// that case, an error with the path of the offending difference is returned and the tag is
+    /// left unchanged.
+    pub fn apply_patch(&mut self, patch: &[Difference]) -> Result<(), ErrorPath<PatchError>> {
+        let mut patched = self.clone();
+        for difference in patch {
+            apply(&mut patched, difference)
+                .map_err(|err| ErrorPath::new_with_path(err, difference.path().clone()))?;
+        }
+        *self = patched;
+        Ok(())
+    }
+}
+
+fn diff_into(old: &NBTTag, new: &NBTTag, path: &Path, out: &mut Vec<Difference>) {
+    let child = |part: PathPart| {
+        let mut path = path.clone();
+        path.push_back(part);
+        path
+    };
+    match (old, new) {
+        (NBTTag::Compound(old), NBTTag::Compound(new)) => {
+            for (key, value) in old.iter() {
+                let path = child(PathPart::MapKey(key.clone()));
+                match new.get(key) {
+                    Some(new_value) => diff_into(value, new_value, &path, out),
+                    None => out.push(Difference::Removed { path }),
+                }
+            }
+            for (key, value) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                out.push(Difference::Added {
+                    path: child(PathPart::MapKey(key.clone())),
+                    value: value.clone(),
+                });
+            }
+        }
+        (NBTTag::List(old), NBTTag::List(new)) if old.len() == new.len() => {
+            for (i, (old, new)) in old.iter().zip(new.iter()).enumerate() {
+                diff_into(old, new, &child(PathPart::Element(i)), out);
+            }
+        }
+        (NBTTag::Float(a), NBTTag::Float(b)) if a.bits_eq(b) => {}
+        (NBTTag::Double(a), NBTTag::Double(b)) if a.bits_eq(b) => {}
+        (NBTTag::Float(_) | NBTTag::Double(_), _) => out.push(Difference::Changed {
+            path: path.clone(),
+            value: new.clone(),
+        }),
+        (old, new) if old != new => out.push(Difference::Changed {
+            path: path.clone(),
+            value: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn apply(tag: &mut NBTTag, difference: &Difference) -> Result<(), PatchError> {
+    let path = difference.path();
+    if let Difference::Changed { value, .. } = difference {
+        *tag.get_parts_mut(&path.0).ok_or(PatchError::MissingTag)? = value.clone();
+        return Ok(());
+    }
+
+    let last = path.0.back().ok_or(PatchError::InvalidPath)?;
+    let parent = tag
+        .get_parts_mut(path.0.range(..path.0.len() - 1))
+        .ok_or(PatchError::MissingTag)?;
+    match (difference, parent, last) {
+        (Difference::Added { value, .. }, NBTTag::Compound(v), part) => {
+            if v.get_part(part).is_some() {
+                return Err(PatchError::AlreadyExists);
+            }
+            v.set_part(part, 0, value.clone())
+                .map_err(|_| PatchError::InvalidPath)?;
+        }
+        (Difference::Added { value, .. }, NBTTag::List(v), PathPart::Element(i)) => {
+            if *i > v.len() {
+                return Err(PatchError::MissingTag);
+            }
+            v.insert(*i, value.clone());
+        }
+        (Difference::Removed { .. }, NBTTag::Compound(v), part) => {
+            v.remove_part(part).ok_or(PatchError::MissingTag)?;
+        }
+        (Difference::Removed { .. }, NBTTag::List(v), PathPart::Element(i)) => {
+            if *i >= v.len() {
+                return Err(PatchError::MissingTag);
+            }
+            v.remove(*i);
+        }
+        _ => return Err(PatchError::InvalidPath),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::Difference;
+    use crate::err::{PatchError, Path};
+    use crate::NBTTag;
+
+    fn path(s: &str) -> Path {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = nbt!({
+            "name": "Zuri",
+            "pos": [1i32, 2i32, 3i32],
+            "items": [{"id": 1i16}],
+            "removed": 1i8,
+            "nan": f64::NAN,
+        });
+        let new = nbt!({
+            "name": "Zuri",
+            "pos": [1i32, 5i32, 3i32],
+            "items": [{"id": 1i16}, {"id": 2i16}],
+            "nan": f64::NAN,
+            "added": {"a": 1i8},
+        });
+        let patch = old.diff(&new);
+        assert_eq!(
+            patch,
+            [
+                Difference::Changed {
+                    path: path("pos[1]"),
+                    value: NBTTag::from(5i32),
+                },
+                Difference::Changed {
+                    path: path("items"),
+                    value: nbt!([{"id": 1i16}, {"id": 2i16}]),
+                },
+                Difference::Removed {
+                    path: path("removed"),
+                },
+                Difference::Added {
+                    path: path("added"),
+                    value: nbt!({"a": 1i8}),
+                },
+            ]
+        );
+        assert!(old.diff(&old).is_empty());
+
+        let mut patched = old.clone();
+        patched.apply_patch(&patch).unwrap();
+        assert_eq!(patched.diff(&new), []);
+
+        assert_eq!(
+            NBTTag::from(1i32).diff(&NBTTag::from(1i64)),
+            [Difference::Changed {
+                path: Path::default(),
+                value: NBTTag::from(1i64),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_apply_patch_conflicts() {
+        let base = nbt!({"a": {"b": 1i8}, "list": [1i32]});
+
+        let mut nbt = base.clone();
+        let patch = [
+            Difference::Changed {
+                path: path("a.b"),
+                value: NBTTag::from(2i8),
+            },
+            Difference::Removed { path: path("a.c") },
+        ];
+        let err = nbt.apply_patch(&patch).unwrap_err();
+        assert_eq!(err.inner, PatchError::MissingTag);
+        assert_eq!(err.path, path("a.c"));
+        assert_eq!(nbt, base);
+
+        let patch = [Difference::Added {
+            path: path("a.b"),
+            value: NBTTag::from(2i8),
+        }];
+        let err = nbt.apply_patch(&patch).unwrap_err();
+        assert_eq!(err.inner, PatchError::AlreadyExists);
+
+        let patch = [Difference::Removed {
+            path: path("list.a"),
+        }];
+        let err = nbt.apply_patch(&patch).unwrap_err();
+        assert_eq!(err.inner, PatchError::InvalidPath);
+
+        let patch = [
+            Difference::Added {
+                path: path("list[1]"),
+                value: NBTTag::from(2i32),
+            },
+            Difference::Removed {
+                path: path("list[0]"),
+            },
+        ];
+        nbt.apply_patch(&patch).unwrap();
+        assert_eq!(nbt, nbt!({"a": {"b": 1i8}, "list": [2i32]}));
+    }
+}
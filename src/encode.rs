@@ -2,10 +2,58 @@
 use std::io::Write;
 
 use crate::err::{ErrorPath, PathPart, WriteError};
+use crate::tag::ids;
 
 /// A short notation for the result type used in the [Writer].
 pub type Res = Result<(), ErrorPath<WriteError>>;
 
+/// Options that alter how NBT data is written using [crate::NBTTag::write_with].
+#[derive(Debug, Default, Clone)]
+pub struct WriteOpts {
+    /// The name given to the root tag. An empty name is written if this is `None`.
+    pub root_name: Option<String>,
+    /// Whether the name of the root tag should be omitted entirely, in which case the type of the
+    /// root tag is directly followed by its payload. This takes precedence over [Self::root_name].
+    pub omit_root_name: bool,
+    /// An explicit order in which the entries of compound tags are written, for example to match
+    /// the layout of a file written by another implementation byte for byte.
+    ///
+    /// Entries with a key in this list are written first, in the order of the list. All other
+    /// entries are written after them, in their order in the compound. The order applies to every
+    /// compound tag, regardless of how deeply it is nested.
+    pub key_order: Option<Vec<String>>,
+    /// Whether long array tags should be rejected with a [WriteError::UnsupportedTag] error,
+    /// for writing data for Minecraft: Java Edition versions before 1.12, which do not support
+    /// long array tags.
+    ///
+    /// Bytes that were already written before the long array was encountered are not undone, so
+    /// write into a buffer in memory first if nothing should be written on failure.
+    pub legacy_no_long_array: bool,
+}
+
+impl WriteOpts {
+    /// Writes the type and, unless omitted, the name of the root tag.
+    pub(crate) fn write_header(&self, buf: &mut impl Write, w: &impl Writer, tag_id: u8) -> Res {
+        w.write_u8(buf, tag_id)?;
+        if !self.omit_root_name {
+            w.write_root_name(buf, self.root_name.as_deref().unwrap_or_default())?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the number of bytes a character occupies in the modified UTF-8 encoding used for
+/// strings.
+pub(crate) fn modified_utf8_len(c: char) -> usize {
+    match c as u32 {
+        0 => 2,
+        0x01..=0x7f => 1,
+        0x80..=0x7ff => 2,
+        0x800..=0xffff => 3,
+        _ => 6,
+    }
+}
+
 /// A trait that can be implemented to alter how basic NBT types are written.
 ///
 /// All the implemented methods must not panic.
@@ -27,7 +75,7 @@ pub trait Writer {
 
     /// Writes the NBT `end` tag, which indicates the end of a compound tag.
     fn write_end(&self, buf: &mut impl Write) -> Res {
-        self.write_u8(buf, 0)
+        self.write_u8(buf, ids::END)
     }
 
     /// Writes a variable-length string.
@@ -48,6 +96,13 @@ pub trait Writer {
         Ok(())
     }
 
+    /// Writes the name of the root tag, which directly follows the type of the root tag.
+    ///
+    /// Encodings that do not name the root tag should override this method to write nothing.
+    fn write_root_name(&self, buf: &mut impl Write, x: &str) -> Res {
+        self.write_string(buf, x)
+    }
+
     /// Writes variable-length array of 8-bit signed integers.
     fn write_i8_vec(&self, buf: &mut impl Write, x: &[i8]) -> Res {
         if x.len() > i32::MAX as usize {
@@ -112,3 +167,220 @@ pub trait Writer {
         Ok(())
     }
 }
+
+/// An object-safe version of the [Writer] trait, which allows the encoding to be chosen at runtime
+/// using trait objects such as `Box<dyn DynWriter>`.
+///
+/// This trait is implemented for every [Writer], and [Writer] is in turn implemented for
+/// `dyn DynWriter`, as well as for references and boxes of it. This means a trait object can be
+/// passed anywhere a [Writer] is expected.
+pub trait DynWriter {
+    /// Writes an 8-bit unsigned integer.
+    fn dyn_write_u8(&self, buf: &mut dyn Write, x: u8) -> Res;
+    /// Writes an 8-bit signed integer.
+    fn dyn_write_i8(&self, buf: &mut dyn Write, x: i8) -> Res;
+    /// Writes a 16-bit signed integer.
+    fn dyn_write_i16(&self, buf: &mut dyn Write, x: i16) -> Res;
+    /// Writes a 32-bit signed integer.
+    fn dyn_write_i32(&self, buf: &mut dyn Write, x: i32) -> Res;
+    /// Writes a 64-bit signed integer.
+    fn dyn_write_i64(&self, buf: &mut dyn Write, x: i64) -> Res;
+    /// Writes a 32-bit floating point number.
+    fn dyn_write_f32(&self, buf: &mut dyn Write, x: f32) -> Res;
+    /// Writes a 64-bit floating point number.
+    fn dyn_write_f64(&self, buf: &mut dyn Write, x: f64) -> Res;
+    /// Writes the NBT `end` tag.
+    fn dyn_write_end(&self, buf: &mut dyn Write) -> Res;
+    /// Writes a variable-length string.
+    fn dyn_write_string(&self, buf: &mut dyn Write, x: &str) -> Res;
+    /// Writes the name of the root tag.
+    fn dyn_write_root_name(&self, buf: &mut dyn Write, x: &str) -> Res;
+    /// Writes variable-length array of 8-bit signed integers.
+    fn dyn_write_i8_vec(&self, buf: &mut dyn Write, x: &[i8]) -> Res;
+    /// Writes variable-length array of 8-bit unsigned integers.
+    fn dyn_write_u8_vec(&self, buf: &mut dyn Write, x: &[u8]) -> Res;
+    /// Writes variable-length array of 32-bit signed integers.
+    fn dyn_write_i32_vec(&self, buf: &mut dyn Write, x: &[i32]) -> Res;
+    /// Writes variable-length array of 64-bit signed integers.
+    fn dyn_write_i64_vec(&self, buf: &mut dyn Write, x: &[i64]) -> Res;
+}
+
+impl<T: Writer> DynWriter for T {
+    fn dyn_write_u8(&self, mut buf: &mut dyn Write, x: u8) -> Res {
+        self.write_u8(&mut buf, x)
+    }
+
+    fn dyn_write_i8(&self, mut buf: &mut dyn Write, x: i8) -> Res {
+        self.write_i8(&mut buf, x)
+    }
+
+    fn dyn_write_i16(&self, mut buf: &mut dyn Write, x: i16) -> Res {
+        self.write_i16(&mut buf, x)
+    }
+
+    fn dyn_write_i32(&self, mut buf: &mut dyn Write, x: i32) -> Res {
+        self.write_i32(&mut buf, x)
+    }
+
+    fn dyn_write_i64(&self, mut buf: &mut dyn Write, x: i64) -> Res {
+        self.write_i64(&mut buf, x)
+    }
+
+    fn dyn_write_f32(&self, mut buf: &mut dyn Write, x: f32) -> Res {
+        self.write_f32(&mut buf, x)
+    }
+
+    fn dyn_write_f64(&self, mut buf: &mut dyn Write, x: f64) -> Res {
+        self.write_f64(&mut buf, x)
+    }
+
+    fn dyn_write_end(&self, mut buf: &mut dyn Write) -> Res {
+        self.write_end(&mut buf)
+    }
+
+    fn dyn_write_string(&self, mut buf: &mut dyn Write, x: &str) -> Res {
+        self.write_string(&mut buf, x)
+    }
+
+    fn dyn_write_root_name(&self, mut buf: &mut dyn Write, x: &str) -> Res {
+        self.write_root_name(&mut buf, x)
+    }
+
+    fn dyn_write_i8_vec(&self, mut buf: &mut dyn Write, x: &[i8]) -> Res {
+        self.write_i8_vec(&mut buf, x)
+    }
+
+    fn dyn_write_u8_vec(&self, mut buf: &mut dyn Write, x: &[u8]) -> Res {
+        self.write_u8_vec(&mut buf, x)
+    }
+
+    fn dyn_write_i32_vec(&self, mut buf: &mut dyn Write, x: &[i32]) -> Res {
+        self.write_i32_vec(&mut buf, x)
+    }
+
+    fn dyn_write_i64_vec(&self, mut buf: &mut dyn Write, x: &[i64]) -> Res {
+        self.write_i64_vec(&mut buf, x)
+    }
+}
+
+impl Writer for dyn DynWriter + '_ {
+    fn write_u8(&self, buf: &mut impl Write, x: u8) -> Res {
+        self.dyn_write_u8(buf, x)
+    }
+
+    fn write_i8(&self, buf: &mut impl Write, x: i8) -> Res {
+        self.dyn_write_i8(buf, x)
+    }
+
+    fn write_i16(&self, buf: &mut impl Write, x: i16) -> Res {
+        self.dyn_write_i16(buf, x)
+    }
+
+    fn write_i32(&self, buf: &mut impl Write, x: i32) -> Res {
+        self.dyn_write_i32(buf, x)
+    }
+
+    fn write_i64(&self, buf: &mut impl Write, x: i64) -> Res {
+        self.dyn_write_i64(buf, x)
+    }
+
+    fn write_f32(&self, buf: &mut impl Write, x: f32) -> Res {
+        self.dyn_write_f32(buf, x)
+    }
+
+    fn write_f64(&self, buf: &mut impl Write, x: f64) -> Res {
+        self.dyn_write_f64(buf, x)
+    }
+
+    fn write_end(&self, buf: &mut impl Write) -> Res {
+        self.dyn_write_end(buf)
+    }
+
+    fn write_string(&self, buf: &mut impl Write, x: &str) -> Res {
+        self.dyn_write_string(buf, x)
+    }
+
+    fn write_root_name(&self, buf: &mut impl Write, x: &str) -> Res {
+        self.dyn_write_root_name(buf, x)
+    }
+
+    fn write_i8_vec(&self, buf: &mut impl Write, x: &[i8]) -> Res {
+        self.dyn_write_i8_vec(buf, x)
+    }
+
+    fn write_u8_vec(&self, buf: &mut impl Write, x: &[u8]) -> Res {
+        self.dyn_write_u8_vec(buf, x)
+    }
+
+    fn write_i32_vec(&self, buf: &mut impl Write, x: &[i32]) -> Res {
+        self.dyn_write_i32_vec(buf, x)
+    }
+
+    fn write_i64_vec(&self, buf: &mut impl Write, x: &[i64]) -> Res {
+        self.dyn_write_i64_vec(buf, x)
+    }
+}
+
+macro_rules! impl_deref_writer {
+    ($($typ:ty$(,)?)*) => {
+        $(impl<W: Writer + ?Sized> Writer for $typ {
+            fn write_u8(&self, buf: &mut impl Write, x: u8) -> Res {
+                (**self).write_u8(buf, x)
+            }
+
+            fn write_i8(&self, buf: &mut impl Write, x: i8) -> Res {
+                (**self).write_i8(buf, x)
+            }
+
+            fn write_i16(&self, buf: &mut impl Write, x: i16) -> Res {
+                (**self).write_i16(buf, x)
+            }
+
+            fn write_i32(&self, buf: &mut impl Write, x: i32) -> Res {
+                (**self).write_i32(buf, x)
+            }
+
+            fn write_i64(&self, buf: &mut impl Write, x: i64) -> Res {
+                (**self).write_i64(buf, x)
+            }
+
+            fn write_f32(&self, buf: &mut impl Write, x: f32) -> Res {
+                (**self).write_f32(buf, x)
+            }
+
+            fn write_f64(&self, buf: &mut impl Write, x: f64) -> Res {
+                (**self).write_f64(buf, x)
+            }
+
+            fn write_end(&self, buf: &mut impl Write) -> Res {
+                (**self).write_end(buf)
+            }
+
+            fn write_string(&self, buf: &mut impl Write, x: &str) -> Res {
+                (**self).write_string(buf, x)
+            }
+
+            fn write_root_name(&self, buf: &mut impl Write, x: &str) -> Res {
+                (**self).write_root_name(buf, x)
+            }
+
+            fn write_i8_vec(&self, buf: &mut impl Write, x: &[i8]) -> Res {
+                (**self).write_i8_vec(buf, x)
+            }
+
+            fn write_u8_vec(&self, buf: &mut impl Write, x: &[u8]) -> Res {
+                (**self).write_u8_vec(buf, x)
+            }
+
+            fn write_i32_vec(&self, buf: &mut impl Write, x: &[i32]) -> Res {
+                (**self).write_i32_vec(buf, x)
+            }
+
+            fn write_i64_vec(&self, buf: &mut impl Write, x: &[i64]) -> Res {
+                (**self).write_i64_vec(buf, x)
+            }
+        })*
+    };
+}
+
+impl_deref_writer!(&W, Box<W>);
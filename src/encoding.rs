@@ -4,12 +4,14 @@
 //!  - [BigEndian]
 //!  - [LittleEndian]
 //!  - [NetworkLittleEndian]
+//!  - [NetworkBigEndian]
 use crate::decode::Reader;
 use crate::encode::Writer;
 use crate::err::{ErrorPath, PathPart, ReadError, WriteError};
-use crate::{decode, encode};
-use byteorder::{ReadBytesExt, WriteBytesExt};
-use std::io::{Read, Write};
+use crate::tag::ids;
+use crate::{decode, encode, NBTTagType};
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
+use std::io::{self, Read, Write};
 
 /// An NBT encoding that encodes all basic types using big endian encoding.
 ///
@@ -33,6 +35,157 @@ pub struct LittleEndian;
 #[derive(Debug, Default, Clone)]
 pub struct NetworkLittleEndian;
 
+/// An NBT encoding that encodes all basic types using big endian encoding, just like [BigEndian],
+/// but that does not write the name of the root tag. The type of the root tag is directly followed
+/// by its payload.
+///
+/// This format is used for nbt sent in Minecraft: Java Edition's protocol since version 1.20.2.
+/// Any root name provided while writing, such as through [crate::encode::WriteOpts::root_name],
+/// is ignored.
+#[derive(Debug, Default, Clone)]
+pub struct NetworkBigEndian;
+
+/// The byte order of fixed-size encoded NBT data, as detected by [detect_endianness].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Endianness {
+    /// The data is likely encoded using the [BigEndian] encoding.
+    Big,
+    /// The data is likely encoded using the [LittleEndian] encoding.
+    Little,
+}
+
+/// Guesses whether the NBT data is encoded using the [BigEndian] or the [LittleEndian] encoding,
+/// which is useful to suggest the right encoding when reading a file fails.
+///
+/// This is a heuristic that reads the length of the root tag name under both byte orders, and picks
+/// the one that results in a sane name length. If both byte orders agree on the length, such as
+/// for the empty names that are common for root tags, the name of the first entry of a root
+/// compound tag is checked instead.
+///
+/// Returns [None] if the data does not look like valid NBT in either byte order, or if the byte
+/// order could not be determined.
+pub fn detect_endianness(data: &[u8]) -> Option<Endianness> {
+    let mut offset = 0;
+    loop {
+        let tag_id = *data.get(offset)?;
+        NBTTagType::from_id(tag_id)?;
+        let big = sane_name_len(data, offset + 1, Endianness::Big);
+        let little = sane_name_len(data, offset + 1, Endianness::Little);
+        match (big, little) {
+            (Some(big), Some(little)) if big == little => {
+                if tag_id != ids::COMPOUND {
+                    return None;
+                }
+                offset += 3 + big;
+            }
+            (Some(big), Some(little)) if big < little => return Some(Endianness::Big),
+            (Some(_), Some(_)) => return Some(Endianness::Little),
+            (Some(_), None) => return Some(Endianness::Big),
+            (None, Some(_)) => return Some(Endianness::Little),
+            (None, None) => return None,
+        }
+    }
+}
+
+/// Reads the length of a tag name at the offset using the byte order, returning it only if the
+/// name fits in the data and is a valid string.
+fn sane_name_len(data: &[u8], offset: usize, endianness: Endianness) -> Option<usize> {
+    let bytes = data.get(offset..offset + 2)?;
+    let len = match endianness {
+        Endianness::Big => byteorder::BigEndian::read_i16(bytes),
+        Endianness::Little => byteorder::LittleEndian::read_i16(bytes),
+    };
+    let len = usize::try_from(len).ok()?;
+    let name = data.get(offset + 2..offset + 2 + len)?;
+    cesu8::from_java_cesu8(name).ok()?;
+    Some(len)
+}
+
+/// The size in bytes of the buffer that integer arrays are converted into before being written,
+/// or converted from after being read.
+const CHUNK_LEN: usize = 4096;
+
+/// Writes a length-prefixed array of 32-bit signed integers using the byte order `B`, converting
+/// it into bytes in fixed-size chunks rather than one element at a time.
+fn write_i32_slice<B: ByteOrder>(w: &impl Writer, buf: &mut impl Write, x: &[i32]) -> encode::Res {
+    if x.len() > i32::MAX as usize {
+        return Err(ErrorPath::new(WriteError::SeqLengthViolation(
+            i32::MAX as usize,
+            x.len(),
+        )));
+    }
+    w.write_i32(buf, x.len() as i32)?;
+
+    let mut bytes = [0; CHUNK_LEN];
+    for chunk in x.chunks(CHUNK_LEN / 4) {
+        let bytes = &mut bytes[..chunk.len() * 4];
+        B::write_i32_into(chunk, bytes);
+        buf.write_all(bytes).map_err(|x| ErrorPath::new(x.into()))?;
+    }
+    Ok(())
+}
+
+/// Writes a length-prefixed array of 64-bit signed integers using the byte order `B`, converting
+/// it into bytes in fixed-size chunks rather than one element at a time.
+fn write_i64_slice<B: ByteOrder>(w: &impl Writer, buf: &mut impl Write, x: &[i64]) -> encode::Res {
+    if x.len() > i32::MAX as usize {
+        return Err(ErrorPath::new(WriteError::SeqLengthViolation(
+            i32::MAX as usize,
+            x.len(),
+        )));
+    }
+    w.write_i32(buf, x.len() as i32)?;
+
+    let mut bytes = [0; CHUNK_LEN];
+    for chunk in x.chunks(CHUNK_LEN / 8) {
+        let bytes = &mut bytes[..chunk.len() * 8];
+        B::write_i64_into(chunk, bytes);
+        buf.write_all(bytes).map_err(|x| ErrorPath::new(x.into()))?;
+    }
+    Ok(())
+}
+
+/// Reads a length-prefixed array of integers that are `size` bytes long, reading the bytes in
+/// fixed-size chunks rather than one element at a time and converting each element using `convert`.
+fn read_int_vec<T>(
+    r: &impl Reader,
+    buf: &mut impl Read,
+    size: usize,
+    convert: fn(&[u8]) -> T,
+) -> decode::Res<Vec<T>> {
+    let len = r.i32(buf)?;
+    let len = usize::try_from(len).map_err(|_| {
+        ErrorPath::new(ReadError::SeqLengthViolation(
+            i32::MAX as usize,
+            len as usize,
+        ))
+    })?;
+
+    let mut vec = Vec::with_capacity(decode::prealloc_capacity::<T>(len));
+    let mut bytes = [0; CHUNK_LEN];
+    while vec.len() < len {
+        let bytes = &mut bytes[..(len - vec.len()).min(CHUNK_LEN / size) * size];
+        let mut filled = 0;
+        while filled < bytes.len() {
+            let err = match buf.read(&mut bytes[filled..]) {
+                Ok(0) => io::ErrorKind::UnexpectedEof.into(),
+                Ok(n) => {
+                    filled += n;
+                    continue;
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => err,
+            };
+            // Report the element that could not be read completely, like reading it on its own.
+            return Err(
+                ErrorPath::new(err.into()).prepend(PathPart::Element(vec.len() + filled / size))
+            );
+        }
+        vec.extend(bytes.chunks_exact(size).map(convert));
+    }
+    Ok(vec)
+}
+
 impl Reader for BigEndian {
     fn u8(&self, buf: &mut impl Read) -> decode::Res<u8> {
         buf.read_u8().map_err(|x| ErrorPath::new(x.into()))
@@ -66,6 +219,18 @@ impl Reader for BigEndian {
         buf.read_f64::<byteorder::BigEndian>()
             .map_err(|x| ErrorPath::new(x.into()))
     }
+
+    fn i8_vec(&self, buf: &mut impl Read) -> decode::Res<Vec<i8>> {
+        read_int_vec(self, buf, 1, |x| x[0] as i8)
+    }
+
+    fn i32_vec(&self, buf: &mut impl Read) -> decode::Res<Vec<i32>> {
+        read_int_vec(self, buf, 4, byteorder::BigEndian::read_i32)
+    }
+
+    fn i64_vec(&self, buf: &mut impl Read) -> decode::Res<Vec<i64>> {
+        read_int_vec(self, buf, 8, byteorder::BigEndian::read_i64)
+    }
 }
 
 impl Writer for BigEndian {
@@ -101,6 +266,14 @@ impl Writer for BigEndian {
         buf.write_f64::<byteorder::BigEndian>(x)
             .map_err(|x| ErrorPath::new(x.into()))
     }
+
+    fn write_i32_vec(&self, buf: &mut impl Write, x: &[i32]) -> encode::Res {
+        write_i32_slice::<byteorder::BigEndian>(self, buf, x)
+    }
+
+    fn write_i64_vec(&self, buf: &mut impl Write, x: &[i64]) -> encode::Res {
+        write_i64_slice::<byteorder::BigEndian>(self, buf, x)
+    }
 }
 
 impl Reader for LittleEndian {
@@ -136,6 +309,18 @@ impl Reader for LittleEndian {
         buf.read_f64::<byteorder::LittleEndian>()
             .map_err(|x| ErrorPath::new(x.into()))
     }
+
+    fn i8_vec(&self, buf: &mut impl Read) -> decode::Res<Vec<i8>> {
+        read_int_vec(self, buf, 1, |x| x[0] as i8)
+    }
+
+    fn i32_vec(&self, buf: &mut impl Read) -> decode::Res<Vec<i32>> {
+        read_int_vec(self, buf, 4, byteorder::LittleEndian::read_i32)
+    }
+
+    fn i64_vec(&self, buf: &mut impl Read) -> decode::Res<Vec<i64>> {
+        read_int_vec(self, buf, 8, byteorder::LittleEndian::read_i64)
+    }
 }
 
 impl Writer for LittleEndian {
@@ -171,6 +356,14 @@ impl Writer for LittleEndian {
         buf.write_f64::<byteorder::LittleEndian>(x)
             .map_err(|x| ErrorPath::new(x.into()))
     }
+
+    fn write_i32_vec(&self, buf: &mut impl Write, x: &[i32]) -> encode::Res {
+        write_i32_slice::<byteorder::LittleEndian>(self, buf, x)
+    }
+
+    fn write_i64_vec(&self, buf: &mut impl Write, x: &[i64]) -> encode::Res {
+        write_i64_slice::<byteorder::LittleEndian>(self, buf, x)
+    }
 }
 
 impl Reader for NetworkLittleEndian {
@@ -195,7 +388,7 @@ impl Reader for NetworkLittleEndian {
             v |= ((b & 0x7f) as u32) << i;
             if b & 0x80 == 0 {
                 let x = (v >> 1) as i32;
-                return Ok(if v & 1 != 0 { -x } else { x });
+                return Ok(if v & 1 != 0 { !x } else { x });
             }
         }
         Err(ErrorPath::new(ReadError::Custom(
@@ -211,7 +404,7 @@ impl Reader for NetworkLittleEndian {
             v |= ((b & 0x7f) as u64) << i;
             if b & 0x80 == 0 {
                 let x = (v >> 1) as i64;
-                return Ok(if v & 1 != 0 { -x } else { x });
+                return Ok(if v & 1 != 0 { !x } else { x });
             }
         }
         Err(ErrorPath::new(ReadError::Custom(
@@ -229,34 +422,107 @@ impl Reader for NetworkLittleEndian {
             .map_err(|x| ErrorPath::new(x.into()))
     }
 
-    fn string(&self, buf: &mut impl Read) -> decode::Res<String> {
-        let len = 'var_len: {
-            let mut v: u32 = 0;
-            for i in (0..35).step_by(7) {
-                let b = self.u8(buf)?;
+    fn string_len(&self, buf: &mut impl Read) -> decode::Res<usize> {
+        let mut v: u32 = 0;
+        for i in (0..35).step_by(7) {
+            let b = self.u8(buf)?;
 
-                v |= ((b & 0x7f) as u32) << i;
-                if b & 0x80 == 0 {
-                    break 'var_len v;
-                }
+            v |= ((b & 0x7f) as u32) << i;
+            if b & 0x80 == 0 {
+                return Ok(v as usize);
             }
-            return Err(ErrorPath::new(ReadError::Custom(
-                "varint overflows integer".to_string(),
-            )));
-        };
-
-        let mut str_buf = Vec::with_capacity(len as usize);
-        for i in 0..len {
-            str_buf.push(
-                self.u8(buf)
-                    .map_err(|err| err.prepend(PathPart::Element(i as usize)))?,
-            );
         }
+        Err(ErrorPath::new(ReadError::Custom(
+            "varint overflows integer".to_string(),
+        )))
+    }
+}
 
-        match cesu8::from_java_cesu8(&str_buf) {
-            Ok(str) => Ok(str.into_owned()),
-            Err(_) => Err(ErrorPath::new(ReadError::InvalidString(str_buf))),
-        }
+impl Reader for NetworkBigEndian {
+    fn u8(&self, buf: &mut impl Read) -> decode::Res<u8> {
+        BigEndian.u8(buf)
+    }
+
+    fn i8(&self, buf: &mut impl Read) -> decode::Res<i8> {
+        BigEndian.i8(buf)
+    }
+
+    fn i16(&self, buf: &mut impl Read) -> decode::Res<i16> {
+        BigEndian.i16(buf)
+    }
+
+    fn i32(&self, buf: &mut impl Read) -> decode::Res<i32> {
+        BigEndian.i32(buf)
+    }
+
+    fn i64(&self, buf: &mut impl Read) -> decode::Res<i64> {
+        BigEndian.i64(buf)
+    }
+
+    fn f32(&self, buf: &mut impl Read) -> decode::Res<f32> {
+        BigEndian.f32(buf)
+    }
+
+    fn f64(&self, buf: &mut impl Read) -> decode::Res<f64> {
+        BigEndian.f64(buf)
+    }
+
+    fn i8_vec(&self, buf: &mut impl Read) -> decode::Res<Vec<i8>> {
+        BigEndian.i8_vec(buf)
+    }
+
+    fn i32_vec(&self, buf: &mut impl Read) -> decode::Res<Vec<i32>> {
+        BigEndian.i32_vec(buf)
+    }
+
+    fn i64_vec(&self, buf: &mut impl Read) -> decode::Res<Vec<i64>> {
+        BigEndian.i64_vec(buf)
+    }
+
+    fn root_name(&self, _buf: &mut impl Read) -> decode::Res<String> {
+        Ok(String::new())
+    }
+}
+
+impl Writer for NetworkBigEndian {
+    fn write_u8(&self, buf: &mut impl Write, x: u8) -> encode::Res {
+        BigEndian.write_u8(buf, x)
+    }
+
+    fn write_i8(&self, buf: &mut impl Write, x: i8) -> encode::Res {
+        BigEndian.write_i8(buf, x)
+    }
+
+    fn write_i16(&self, buf: &mut impl Write, x: i16) -> encode::Res {
+        BigEndian.write_i16(buf, x)
+    }
+
+    fn write_i32(&self, buf: &mut impl Write, x: i32) -> encode::Res {
+        BigEndian.write_i32(buf, x)
+    }
+
+    fn write_i64(&self, buf: &mut impl Write, x: i64) -> encode::Res {
+        BigEndian.write_i64(buf, x)
+    }
+
+    fn write_f32(&self, buf: &mut impl Write, x: f32) -> encode::Res {
+        BigEndian.write_f32(buf, x)
+    }
+
+    fn write_f64(&self, buf: &mut impl Write, x: f64) -> encode::Res {
+        BigEndian.write_f64(buf, x)
+    }
+
+    fn write_i32_vec(&self, buf: &mut impl Write, x: &[i32]) -> encode::Res {
+        BigEndian.write_i32_vec(buf, x)
+    }
+
+    fn write_i64_vec(&self, buf: &mut impl Write, x: &[i64]) -> encode::Res {
+        BigEndian.write_i64_vec(buf, x)
+    }
+
+    fn write_root_name(&self, _buf: &mut impl Write, _x: &str) -> encode::Res {
+        Ok(())
     }
 }
 
@@ -335,10 +601,13 @@ impl Writer for NetworkLittleEndian {
 /// Test all encodings with various data.
 #[cfg(test)]
 mod tests {
-    use crate::decode::Reader;
-    use crate::encode::Writer;
-    use crate::encoding::{BigEndian, LittleEndian, NetworkLittleEndian};
-    use crate::{err, tag, NBTTag};
+    use crate::decode::{DepthPolicy, DynReader, ReadOpts, Reader, DEFAULT_MAX_DEPTH};
+    use crate::encode::{DynWriter, WriteOpts, Writer};
+    use crate::encoding::{
+        detect_endianness, BigEndian, Endianness, LittleEndian, NetworkBigEndian,
+        NetworkLittleEndian,
+    };
+    use crate::{err, tag, NBTTag, NBTTagType};
 
     #[test]
     fn test_big_endian() {
@@ -355,6 +624,36 @@ mod tests {
         test::<NetworkLittleEndian>();
     }
 
+    #[test]
+    fn test_network_big_endian() {
+        test::<NetworkBigEndian>();
+
+        // The payload is identical to big endian, only without the name of the root tag.
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_string("name", "a")
+                .with_long_array("longs", vec![1, -2])
+                .build(),
+        );
+        let mut big = vec![];
+        nbt.write_with_name("root", &mut big, &BigEndian).unwrap();
+        let mut network = vec![];
+        nbt.write_with_name("root", &mut network, &NetworkBigEndian)
+            .unwrap();
+        assert_eq!(network[0], big[0]);
+        assert_eq!(network[1..], big[7..]);
+
+        let (name, read) =
+            NBTTag::read_with_name(&mut network.as_slice(), &NetworkBigEndian).unwrap();
+        assert_eq!(name, "");
+        assert_eq!(read, nbt);
+        let read = tag::Compound::read(&mut network.as_slice(), &NetworkBigEndian).unwrap();
+        assert_eq!(NBTTag::Compound(read), nbt);
+
+        let r: &dyn DynReader = &NetworkBigEndian;
+        assert_eq!(NBTTag::read(&mut network.as_slice(), &r).unwrap(), nbt);
+    }
+
     fn test<T: Reader + Writer + Sized + Default>() {
         let nbt = tag::Compound::builder()
             .with_long("test", 10)
@@ -365,25 +664,468 @@ mod tests {
                 vec![tag::ByteArray(vec![1, 2, 3]), tag::ByteArray(vec![4, 5, 6])],
             )
             .with_list("test4", vec![tag::Byte(1), tag::Byte(3)])
-            .with("test5", tag::Compound::default());
+            .with("test5", tag::Compound::default())
+            .with_int_array("test6", vec![i32::MIN, -1, 0, 1, i32::MAX])
+            .with_long_array("test7", vec![i64::MIN, -1, 0, 1, i64::MAX])
+            .with_list(
+                "test8",
+                vec![
+                    tag::IntArray(vec![-300, 300]),
+                    tag::IntArray(vec![]),
+                    tag::IntArray(vec![i32::MIN]),
+                ],
+            )
+            .with_list(
+                "test9",
+                vec![
+                    tag::List::from(vec![tag::LongArray(vec![-1, i64::MIN, i64::MAX])]),
+                    tag::List::from(vec![tag::List::from(vec![tag::Int(-5)])]),
+                    tag::List::default(),
+                ],
+            )
+            .with_compound(
+                "test10",
+                tag::Compound::builder()
+                    .with_int("int", -64)
+                    .with_long("long", -64)
+                    .with_list("list", vec![tag::LongArray(vec![-2, 2])]),
+            )
+            // Larger than the chunks in which arrays are written.
+            .with_int_array("test11", (-1500..1500).collect::<Vec<i32>>())
+            .with_long_array(
+                "test12",
+                (-1500..1500).map(|x| x << 32).collect::<Vec<i64>>(),
+            );
         let nbt = NBTTag::Compound(nbt.build());
         let mut buf = vec![];
-        nbt.write(&mut buf, &mut T::default()).unwrap();
+        nbt.write(&mut buf, &T::default()).unwrap();
 
         assert_eq!(
-            NBTTag::read(&mut buf.as_slice(), &mut T::default()).unwrap(),
+            NBTTag::read(&mut buf.as_slice(), &T::default()).unwrap(),
             nbt
         );
     }
 
+    #[test]
+    fn test_read_shallow() {
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_int("a", 1)
+                .with_list("b", vec![tag::String::from("x"), tag::String::from("y")])
+                .with_compound(
+                    "c",
+                    tag::Compound::builder().with_long_array("d", vec![1, 2]),
+                )
+                .build(),
+        );
+        let mut buf = vec![];
+        nbt.write(&mut buf, &NetworkLittleEndian).unwrap();
+
+        let mut reader = buf.as_slice();
+        let mut entries = NBTTag::read_shallow(&mut reader, &NetworkLittleEndian).unwrap();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            entries,
+            vec![
+                ("a".to_string(), NBTTagType::Int),
+                ("b".to_string(), NBTTagType::List),
+                ("c".to_string(), NBTTagType::Compound),
+            ]
+        );
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_depth_policy() {
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_int("x", 1)
+                .with_compound(
+                    "a",
+                    tag::Compound::builder()
+                        .with_compound("b", tag::Compound::builder().with_int("c", 1))
+                        .with_list("d", vec![tag::List::from(vec![tag::Int(1)])]),
+                )
+                .build(),
+        );
+        let mut buf = vec![];
+        nbt.write(&mut buf, &BigEndian).unwrap();
+
+        let mut opts = ReadOpts {
+            max_depth: Some(2),
+            depth_policy: DepthPolicy::Error,
+            ..Default::default()
+        };
+        let err = NBTTag::read_with(&mut buf.as_slice(), &BigEndian, &opts).unwrap_err();
+        assert!(matches!(err.inner, err::ReadError::DepthLimitExceeded(2)));
+
+        opts.depth_policy = DepthPolicy::Truncate;
+        let mut reader = buf.as_slice();
+        let truncated = NBTTag::read_with(&mut reader, &BigEndian, &opts).unwrap();
+        assert!(reader.is_empty());
+        let expected = tag::Compound::builder().with_int("x", 1).with_compound(
+            "a",
+            tag::Compound::builder()
+                .with_compound("b", tag::Compound::default())
+                .with_list("d", tag::List::default()),
+        );
+        assert_eq!(truncated, NBTTag::Compound(expected.build()));
+
+        // Truncating very deeply nested data must not overflow the stack.
+        let mut deep = vec![0x09, 0x00, 0x00];
+        for _ in 0..100_000 {
+            deep.extend([0x09, 0x00, 0x00, 0x00, 0x01]);
+        }
+        deep.extend([0x01, 0x00, 0x00, 0x00, 0x00]);
+        let nbt = NBTTag::read_with(&mut deep.as_slice(), &BigEndian, &opts).unwrap();
+        let expected = tag::List::from(vec![tag::List::from(vec![tag::List::default()])]);
+        assert_eq!(nbt, NBTTag::List(expected));
+    }
+
+    #[test]
+    fn test_default_max_depth() {
+        // A list nested `depth` levels deep, of which the innermost list is empty.
+        let nested = |depth: usize| {
+            let mut buf = vec![0x09, 0x00, 0x00];
+            for _ in 1..depth {
+                buf.extend([0x09, 0x00, 0x00, 0x00, 0x01]);
+            }
+            buf.extend([0x01, 0x00, 0x00, 0x00, 0x00]);
+            buf
+        };
+        let buf = nested(DEFAULT_MAX_DEPTH);
+        let nbt = NBTTag::read(&mut buf.as_slice(), &BigEndian).unwrap();
+        assert_eq!(nbt.max_depth(), DEFAULT_MAX_DEPTH);
+
+        let buf = nested(DEFAULT_MAX_DEPTH + 1);
+        let err = NBTTag::read(&mut buf.as_slice(), &BigEndian).unwrap_err();
+        assert!(matches!(
+            err.inner,
+            err::ReadError::DepthLimitExceeded(DEFAULT_MAX_DEPTH)
+        ));
+        assert_eq!(err.path.0.len(), DEFAULT_MAX_DEPTH);
+    }
+
+    #[test]
+    fn test_truncated_array() {
+        let cases = [
+            (NBTTag::ByteArray(tag::ByteArray(vec![1; 6000])), 1, 5000),
+            (
+                NBTTag::IntArray(tag::IntArray((0..2000).collect())),
+                4,
+                1500,
+            ),
+            (
+                NBTTag::LongArray(tag::LongArray((0..2000).collect())),
+                8,
+                1500,
+            ),
+        ];
+        for (nbt, size, index) in cases {
+            let mut buf = vec![];
+            nbt.write(&mut buf, &LittleEndian).unwrap();
+            // Cut the data off halfway through an element that is not in the first chunk, after
+            // the tag id, the empty name and the length.
+            buf.truncate(7 + index * size + size / 2);
+            let err = NBTTag::read(&mut buf.as_slice(), &LittleEndian).unwrap_err();
+            assert!(matches!(
+                &err.inner,
+                err::ReadError::Io(err) if err.kind() == std::io::ErrorKind::UnexpectedEof
+            ));
+            assert_eq!(err.path.to_string(), format!("[{index}]"));
+        }
+    }
+
+    #[test]
+    fn test_float_bits() {
+        test_float_bits_with::<BigEndian>();
+        test_float_bits_with::<LittleEndian>();
+        test_float_bits_with::<NetworkLittleEndian>();
+    }
+
+    fn test_float_bits_with<T: Reader + Writer + Default>() {
+        let floats = [f32::from_bits(0x7fa0_0001), -0.0];
+        for f in floats {
+            let mut buf = vec![];
+            tag::Float(f).write(&mut buf, &T::default()).unwrap();
+            let read = tag::Float::read(&mut buf.as_slice(), &T::default()).unwrap();
+            assert!(read.bits_eq(&tag::Float(f)));
+        }
+        assert!(!tag::Float(-0.0).bits_eq(&tag::Float(0.0)));
+
+        let doubles = [f64::from_bits(0x7ff4_0000_0000_0001), -0.0];
+        for f in doubles {
+            let mut buf = vec![];
+            tag::Double(f).write(&mut buf, &T::default()).unwrap();
+            let read = tag::Double::read(&mut buf.as_slice(), &T::default()).unwrap();
+            assert!(read.bits_eq(&tag::Double(f)));
+        }
+        assert!(!tag::Double(-0.0).bits_eq(&tag::Double(0.0)));
+    }
+
+    #[test]
+    fn test_write_opts() {
+        let nbt = NBTTag::Int(tag::Int(1));
+        let mut buf = vec![];
+        let opts = WriteOpts {
+            root_name: Some("a".to_string()),
+            omit_root_name: false,
+            ..Default::default()
+        };
+        nbt.write_with(&mut buf, &BigEndian, &opts).unwrap();
+        assert_eq!(buf, [0x03, 0x00, 0x01, 0x61, 0x00, 0x00, 0x00, 0x01]);
+
+        let mut buf = vec![];
+        let opts = WriteOpts {
+            root_name: Some("a".to_string()),
+            omit_root_name: true,
+            ..Default::default()
+        };
+        nbt.write_with(&mut buf, &BigEndian, &opts).unwrap();
+        assert_eq!(buf, [0x03, 0x00, 0x00, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn test_legacy_no_long_array() {
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_int_array("ints", vec![1])
+                .with_list("longs", vec![tag::LongArray(vec![1])])
+                .build(),
+        );
+        let opts = WriteOpts {
+            legacy_no_long_array: true,
+            ..Default::default()
+        };
+        let err = nbt.write_with(&mut vec![], &BigEndian, &opts).unwrap_err();
+        assert!(matches!(
+            err.inner,
+            err::WriteError::UnsupportedTag(NBTTagType::LongArray)
+        ));
+        assert_eq!(err.path.to_string(), "longs[0]");
+
+        nbt.write_with(&mut vec![], &BigEndian, &WriteOpts::default())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_key_order() {
+        let inner = tag::Compound::builder()
+            .with_byte("x", 0)
+            .with_byte("c", 1)
+            .build();
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_byte("a", 2)
+                .with_compound("b", inner)
+                .with_byte("c", 3)
+                .build(),
+        );
+        let opts = WriteOpts {
+            key_order: Some(vec![
+                "c".to_string(),
+                "missing".to_string(),
+                "c".to_string(),
+            ]),
+            ..Default::default()
+        };
+        let mut buf = vec![];
+        nbt.write_with(&mut buf, &BigEndian, &opts).unwrap();
+
+        let keys = |nbt: &NBTTag| -> Vec<String> {
+            nbt.view()
+                .iter_compound()
+                .map(|(k, _)| k.to_string())
+                .collect()
+        };
+        let read = NBTTag::read(&mut buf.as_slice(), &BigEndian).unwrap();
+        assert_eq!(read, nbt);
+        assert_eq!(keys(&read), ["c", "a", "b"]);
+        assert_eq!(keys(read.view().at("b").get().unwrap()), ["c", "x"]);
+    }
+
+    #[test]
+    fn test_dyn_encoding() {
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_string("name", "a")
+                .with_int_array("ints", vec![1, -1])
+                .build(),
+        );
+        let encodings: Vec<(Box<dyn DynReader>, Box<dyn DynWriter>)> = vec![
+            (Box::new(BigEndian), Box::new(BigEndian)),
+            (Box::new(LittleEndian), Box::new(LittleEndian)),
+            (Box::new(NetworkLittleEndian), Box::new(NetworkLittleEndian)),
+            (Box::new(NetworkBigEndian), Box::new(NetworkBigEndian)),
+        ];
+        for (r, w) in encodings {
+            let mut buf = vec![];
+            nbt.write(&mut buf, &w).unwrap();
+            assert_eq!(NBTTag::read(&mut buf.as_slice(), &r).unwrap(), nbt);
+        }
+
+        // Methods overridden by an encoding must be used through the trait object as well.
+        let w: &dyn DynWriter = &NetworkLittleEndian;
+        let mut buf = vec![];
+        w.write_string(&mut buf, "a").unwrap();
+        assert_eq!(buf, [0x01, 0x61]);
+    }
+
+    #[test]
+    fn test_detect_endianness() {
+        let nbt = NBTTag::Compound(tag::Compound::builder().with_int("key", 1).build());
+        let mut buf = vec![];
+        nbt.write(&mut buf, &BigEndian).unwrap();
+        assert_eq!(detect_endianness(&buf), Some(Endianness::Big));
+        let mut buf = vec![];
+        nbt.write(&mut buf, &LittleEndian).unwrap();
+        assert_eq!(detect_endianness(&buf), Some(Endianness::Little));
+
+        let opts = WriteOpts {
+            root_name: Some("root".to_string()),
+            omit_root_name: false,
+            ..Default::default()
+        };
+        let mut buf = vec![];
+        NBTTag::Int(tag::Int(1))
+            .write_with(&mut buf, &LittleEndian, &opts)
+            .unwrap();
+        assert_eq!(detect_endianness(&buf), Some(Endianness::Little));
+
+        let mut buf = vec![];
+        NBTTag::Int(tag::Int(1))
+            .write(&mut buf, &BigEndian)
+            .unwrap();
+        assert_eq!(detect_endianness(&buf), None);
+        assert_eq!(detect_endianness(&[0x0d, 0x00, 0x00]), None);
+        assert_eq!(detect_endianness(&[]), None);
+    }
+
+    #[test]
+    fn test_read_root_type() {
+        let list = tag::List::from([1i32, 2]);
+        let mut buf = vec![];
+        list.write(&mut buf, &BigEndian).unwrap();
+        assert_eq!(
+            tag::List::read(&mut buf.as_slice(), &BigEndian).unwrap(),
+            list
+        );
+
+        let err = tag::Compound::read(&mut buf.as_slice(), &BigEndian).unwrap_err();
+        assert!(matches!(
+            err.inner,
+            err::ReadError::UnexpectedTag(tag::ids::COMPOUND, tag::ids::LIST)
+        ));
+    }
+
+    #[test]
+    fn test_read_limited() {
+        let nbt = NBTTag::Int(tag::Int(1));
+        let mut buf = vec![];
+        nbt.write(&mut buf, &BigEndian).unwrap();
+        let len = buf.len();
+        buf.extend([0x0a, 0x00]);
+
+        let mut reader = buf.as_slice();
+        let read = NBTTag::read_limited(&mut reader, &BigEndian, len).unwrap();
+        assert_eq!(read, nbt);
+        assert_eq!(reader, [0x0a, 0x00]);
+
+        let err = NBTTag::read_limited(&mut buf.as_slice(), &BigEndian, len - 1).unwrap_err();
+        assert!(matches!(err.inner, err::ReadError::Io(_)));
+        let err = NBTTag::read_limited(&mut buf.as_slice(), &BigEndian, len + 1).unwrap_err();
+        assert!(matches!(err.inner, err::ReadError::TrailingBytes(1)));
+    }
+
+    #[test]
+    fn test_read_all() {
+        let tags = vec![
+            NBTTag::Compound(
+                tag::Compound::builder()
+                    .with_string("id", "minecraft:cow")
+                    .build(),
+            ),
+            NBTTag::Compound(tag::Compound::builder().with_int("Age", -1).build()),
+            NBTTag::Compound(tag::Compound::default()),
+        ];
+        let mut buf = vec![];
+        for tag in &tags {
+            tag.write(&mut buf, &LittleEndian).unwrap();
+        }
+        assert_eq!(
+            NBTTag::read_all(&mut buf.as_slice(), &LittleEndian).unwrap(),
+            tags
+        );
+        assert!(NBTTag::read_all(&mut [].as_slice(), &LittleEndian)
+            .unwrap()
+            .is_empty());
+
+        buf.pop();
+        let err = NBTTag::read_all(&mut buf.as_slice(), &LittleEndian).unwrap_err();
+        assert_eq!(err.path.0.front(), Some(&err::PathPart::Element(2)));
+    }
+
+    #[test]
+    fn test_large_declared_length() {
+        // A list of i32::MAX compounds followed by a single element must fail to read without
+        // attempting to allocate space for all of them up front.
+        let buf = [0x09, 0x00, 0x00, 0x0a, 0x7f, 0xff, 0xff, 0xff, 0x00];
+        let err = NBTTag::read(&mut buf.as_slice(), &BigEndian).unwrap_err();
+        assert!(matches!(err.inner, err::ReadError::Io(_)));
+        assert_eq!(err.path.0.front(), Some(&err::PathPart::Element(1)));
+
+        let buf = [0x0b, 0x00, 0x00, 0x7f, 0xff, 0xff, 0xff];
+        let err = NBTTag::read(&mut buf.as_slice(), &BigEndian).unwrap_err();
+        assert!(matches!(err.inner, err::ReadError::Io(_)));
+
+        let buf = [0x09, 0x00, 0x00, 0x0a, 0xff, 0xff, 0xff, 0xff];
+        let err = NBTTag::read(&mut buf.as_slice(), &BigEndian).unwrap_err();
+        assert!(matches!(err.inner, err::ReadError::SeqLengthViolation(..)));
+    }
+
+    #[test]
+    fn test_bytes() {
+        let data = [1, 2, 3, 4];
+        let mut reader = data.as_slice();
+        assert_eq!(BigEndian.bytes(&mut reader, 3).unwrap(), [1, 2, 3]);
+        assert_eq!(reader, [4]);
+
+        let err = BigEndian.bytes(&mut data.as_slice(), 5).unwrap_err();
+        assert!(matches!(err.inner, err::ReadError::Io(_)));
+    }
+
+    #[test]
+    fn test_supplementary_characters() {
+        test_supplementary_characters_with::<BigEndian>();
+        test_supplementary_characters_with::<LittleEndian>();
+        test_supplementary_characters_with::<NetworkLittleEndian>();
+
+        // Supplementary characters are written as surrogate pairs, but standard 4-byte UTF-8
+        // sequences are accepted when reading too.
+        let mut buf = vec![];
+        BigEndian.write_string(&mut buf, "😀").unwrap();
+        assert_eq!(buf, [0x00, 0x06, 0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80]);
+        let utf8 = [0x00, 0x04, 0xf0, 0x9f, 0x98, 0x80];
+        assert_eq!(BigEndian.string(&mut utf8.as_slice()).unwrap(), "😀");
+    }
+
+    fn test_supplementary_characters_with<T: Reader + Writer + Default>() {
+        for s in ["😀", "a😀b", "𝄞 and \0 and é"] {
+            let mut buf = vec![];
+            tag::String::from(s).write(&mut buf, &T::default()).unwrap();
+            let read = tag::String::read(&mut buf.as_slice(), &T::default()).unwrap();
+            assert_eq!(read, tag::String::from(s));
+        }
+    }
+
     #[test]
     fn test_invalid_tagtype() {
         let valid_buf: Vec<u8> = vec![0x03, 0x00, 0x01, 0x61, 0x12, 0x34, 0x56, 0x78];
-        let nbt = NBTTag::read(&mut valid_buf.as_slice(), &mut BigEndian).unwrap();
+        let nbt = NBTTag::read(&mut valid_buf.as_slice(), &BigEndian).unwrap();
         assert!(matches!(nbt, NBTTag::Int(tag::Int(0x12345678))));
 
         let invalid_buf: Vec<u8> = vec![0x15, 0x00, 0x01, 0x61, 0x12, 0x34, 0x56, 0x78];
-        let nbt = NBTTag::read(&mut invalid_buf.as_slice(), &mut BigEndian);
+        let nbt = NBTTag::read(&mut invalid_buf.as_slice(), &BigEndian);
         assert!(matches!(
             nbt,
             Err(err::ErrorPath {
@@ -393,30 +1135,550 @@ mod tests {
         ))
     }
 
+    /// A sink that fails once a fixed number of bytes has been written.
+    struct LimitedWriter(usize);
+
+    impl std::io::Write for LimitedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.0 == 0 {
+                return Err(std::io::ErrorKind::BrokenPipe.into());
+            }
+            let len = buf.len().min(self.0);
+            self.0 -= len;
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_error_path() {
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_list(
+                    "list",
+                    vec![
+                        tag::Compound::builder().with_int("a", 1).build(),
+                        tag::Compound::builder().with_int("b", 2).build(),
+                    ],
+                )
+                .build(),
+        );
+        let mut buf = vec![];
+        nbt.write(&mut buf, &BigEndian).unwrap();
+
+        // Fail while writing the value of the `b` key in the second element of the list.
+        let err = nbt
+            .write(&mut LimitedWriter(buf.len() - 4), &BigEndian)
+            .unwrap_err();
+        assert!(matches!(err.inner, err::WriteError::Io(_)));
+        assert_eq!(err.path.to_string(), "list[1].b");
+
+        // Fail while writing the name of the `list` key.
+        let err = nbt.write(&mut LimitedWriter(5), &BigEndian).unwrap_err();
+        assert_eq!(err.path.to_string(), "list");
+    }
+
+    #[test]
+    fn test_string_too_long_path() {
+        let long = "a".repeat(i16::MAX as usize + 1);
+        for name in [
+            tag::String::from(long.as_str()),
+            tag::String::Bytes(long.clone().into_bytes()),
+        ] {
+            let item = |name: Option<tag::String>| {
+                let mut display = tag::Compound::builder();
+                if let Some(name) = name {
+                    display = display.with_string("Name", name);
+                }
+                tag::Compound::builder()
+                    .with_compound(
+                        "tag",
+                        tag::Compound::builder()
+                            .with_compound("display", display.build())
+                            .build(),
+                    )
+                    .build()
+            };
+            let nbt = NBTTag::Compound(
+                tag::Compound::builder()
+                    .with_list("Inventory", vec![item(None), item(None), item(Some(name))])
+                    .build(),
+            );
+            let err = nbt.write(&mut vec![], &BigEndian).unwrap_err();
+            assert!(matches!(
+                err.inner,
+                err::WriteError::SeqLengthViolation(_, len) if len == long.len()
+            ));
+            assert_eq!(err.path.to_string(), "Inventory[2].tag.display.Name");
+        }
+    }
+
+    #[test]
+    fn test_key_too_long() {
+        let key = "k".repeat(i16::MAX as usize + 1);
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_compound("a", tag::Compound::builder().with_int(&key, 1).build())
+                .build(),
+        );
+        let err = nbt.write(&mut vec![], &BigEndian).unwrap_err();
+        assert!(matches!(
+            err.inner,
+            err::WriteError::KeyTooLong(len) if len == key.len()
+        ));
+        assert_eq!(
+            Vec::from(err.path.0),
+            vec![
+                err::PathPart::MapKey("a".to_string()),
+                err::PathPart::MapKey(key.clone())
+            ]
+        );
+
+        // Values that are too long are still reported as such.
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_string("a", key.as_str())
+                .build(),
+        );
+        let err = nbt.write(&mut vec![], &BigEndian).unwrap_err();
+        assert!(matches!(err.inner, err::WriteError::SeqLengthViolation(..)));
+    }
+
+    #[test]
+    fn test_read_chain() {
+        use std::io::Read;
+
+        fn check(e: &(impl Reader + Writer)) {
+            let nbt = nbt!({
+                "name": "Zuri",
+                "list": [1i16, 2i16],
+                "longs": [L; 1, -1],
+                "nested": { "value": 1.5f64 },
+            });
+            let mut buf = vec![];
+            nbt.write(&mut buf, e).unwrap();
+            for i in 0..=buf.len() {
+                let (header, body) = buf.split_at(i);
+                assert_eq!(NBTTag::read(&mut header.chain(body), e).unwrap(), nbt);
+            }
+        }
+
+        check(&BigEndian);
+        check(&LittleEndian);
+        check(&NetworkLittleEndian);
+    }
+
+    /// A reader that returns at most one byte per call, like a slow network stream.
+    struct OneByteReader<'a>(&'a [u8]);
+
+    impl std::io::Read for OneByteReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((b, rest)), Some(out)) => {
+                    *out = *b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_one_byte_at_a_time() {
+        fn check(e: &(impl Reader + Writer)) {
+            let nbt = nbt!({
+                "name": "Zuri 😀",
+                "list": [[1i32, -2i32], []],
+                "bytes": [B; 1, -1],
+                "ints": [I; i32::MIN, i32::MAX],
+                "longs": [L; i64::MIN, i64::MAX],
+                "float": 0.1f32,
+                "double": -0.1f64,
+                "long": i64::MIN,
+            });
+            let mut buf = vec![];
+            nbt.write(&mut buf, e).unwrap();
+            let opts = ReadOpts {
+                max_elements: Some(usize::MAX),
+                ..Default::default()
+            };
+            assert_eq!(NBTTag::read(&mut OneByteReader(&buf), e).unwrap(), nbt);
+            assert_eq!(
+                NBTTag::read_with(&mut OneByteReader(&buf), e, &opts).unwrap(),
+                nbt
+            );
+
+            // A truncated buffer fails instead of producing garbage.
+            let err = NBTTag::read(&mut OneByteReader(&buf[..buf.len() - 1]), e).unwrap_err();
+            assert!(matches!(err.inner, err::ReadError::Io(_)));
+        }
+
+        check(&BigEndian);
+        check(&LittleEndian);
+        check(&NetworkLittleEndian);
+    }
+
+    #[test]
+    fn test_read_with_warnings() {
+        use crate::decode::{Warning, WarningReason};
+
+        #[rustfmt::skip]
+        let buf = [
+            10, 0, 0,
+            // Two int tags with the same key `a`.
+            3, 0, 1, b'a', 0, 0, 0, 1,
+            3, 0, 1, b'a', 0, 0, 0, 2,
+            // An empty list `l` with an unknown element type.
+            9, 0, 1, b'l', 0x7f, 0, 0, 0, 0,
+            // A list `m` containing a compound with a string that is not valid UTF-8.
+            9, 0, 1, b'm', 10, 0, 0, 0, 1,
+            8, 0, 1, b's', 0, 1, 0xff,
+            0,
+            0,
+        ];
+        let (nbt, warnings) =
+            NBTTag::read_with_warnings(&mut buf.as_slice(), &BigEndian, &Default::default())
+                .unwrap();
+        assert_eq!(nbt, NBTTag::read(&mut buf.as_slice(), &BigEndian).unwrap());
+        assert_eq!(
+            warnings,
+            vec![
+                Warning {
+                    path: "a".parse().unwrap(),
+                    reason: WarningReason::DuplicateKey,
+                },
+                Warning {
+                    path: "l".parse().unwrap(),
+                    reason: WarningReason::UnknownEmptyListType(0x7f),
+                },
+                Warning {
+                    path: "m[0].s".parse().unwrap(),
+                    reason: WarningReason::InvalidString,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_string_len() {
+        fn check(e: &(impl Reader + Writer)) {
+            let opts = ReadOpts {
+                max_string_len: Some(4),
+                ..Default::default()
+            };
+            let read = |nbt: &NBTTag| {
+                let mut buf = vec![];
+                nbt.write(&mut buf, e).unwrap();
+                NBTTag::read_with(&mut buf.as_slice(), e, &opts)
+            };
+
+            let nbt = NBTTag::Compound(tag::Compound::builder().with_string("key", "abcd").build());
+            assert_eq!(read(&nbt).unwrap(), nbt);
+
+            let nbt =
+                NBTTag::Compound(tag::Compound::builder().with_string("key", "abcde").build());
+            let err = read(&nbt).unwrap_err();
+            assert!(matches!(
+                err.inner,
+                err::ReadError::SeqLengthViolation(4, 5)
+            ));
+            assert_eq!(err.path.to_string(), "key");
+
+            let nbt = NBTTag::Compound(tag::Compound::builder().with_int("long key", 1).build());
+            let err = read(&nbt).unwrap_err();
+            assert!(matches!(
+                err.inner,
+                err::ReadError::SeqLengthViolation(4, 8)
+            ));
+        }
+
+        check(&BigEndian);
+        check(&NetworkLittleEndian);
+    }
+
+    #[test]
+    fn test_max_elements() {
+        // Ten lists of ten ints each, which contain 110 elements in total.
+        let list = tag::List::from(vec![tag::List::from(vec![1i32; 10]); 10]);
+        let nbt = NBTTag::Compound(tag::Compound::builder().with_list("a", list).build());
+        let mut buf = vec![];
+        nbt.write(&mut buf, &BigEndian).unwrap();
+
+        let mut opts = ReadOpts {
+            max_elements: Some(111),
+            ..Default::default()
+        };
+        assert_eq!(
+            NBTTag::read_with(&mut buf.as_slice(), &BigEndian, &opts).unwrap(),
+            nbt
+        );
+        opts.max_elements = Some(110);
+        let err = NBTTag::read_with(&mut buf.as_slice(), &BigEndian, &opts).unwrap_err();
+        assert!(matches!(
+            err.inner,
+            err::ReadError::ElementLimitExceeded(110)
+        ));
+        assert_eq!(err.path.to_string(), "a[9]");
+
+        // The length of an array is checked before any of its elements are read.
+        let buf = [0x0b, 0x00, 0x00, 0x7f, 0xff, 0xff, 0xff];
+        let err = NBTTag::read_with(&mut buf.as_slice(), &BigEndian, &opts).unwrap_err();
+        assert!(matches!(
+            err.inner,
+            err::ReadError::ElementLimitExceeded(110)
+        ));
+
+        let nbt = NBTTag::IntArray(vec![1, 2, 3].into());
+        let mut buf = vec![];
+        nbt.write(&mut buf, &NetworkLittleEndian).unwrap();
+        let read = NBTTag::read_with(&mut buf.as_slice(), &NetworkLittleEndian, &opts).unwrap();
+        assert_eq!(read, nbt);
+    }
+
+    #[test]
+    fn test_max_bytes() {
+        fn check(e: &(impl Reader + Writer)) {
+            let nbt = NBTTag::Compound(
+                tag::Compound::builder()
+                    .with_string("name", "value")
+                    .with_list("a", tag::List::from(vec![1i32; 10]))
+                    .build(),
+            );
+            let mut buf = vec![];
+            nbt.write(&mut buf, e).unwrap();
+
+            let mut opts = ReadOpts {
+                max_bytes: Some(buf.len()),
+                ..Default::default()
+            };
+            assert_eq!(
+                NBTTag::read_with(&mut buf.as_slice(), e, &opts).unwrap(),
+                nbt
+            );
+            opts.max_bytes = Some(buf.len() - 1);
+            let err = NBTTag::read_with(&mut buf.as_slice(), e, &opts).unwrap_err();
+            assert!(matches!(
+                err.inner,
+                err::ReadError::ByteLimitExceeded(len) if len == buf.len() - 1
+            ));
+        }
+        check(&BigEndian);
+        check(&NetworkLittleEndian);
+
+        // A huge array in an endless buffer stops being read once the budget runs out.
+        let header = [0x0b, 0x00, 0x00, 0x7f, 0xff, 0xff, 0xff];
+        let opts = ReadOpts {
+            max_bytes: Some(1024),
+            ..Default::default()
+        };
+        let mut buf = std::io::Read::chain(header.as_slice(), std::io::repeat(0));
+        let err = NBTTag::read_with(&mut buf, &BigEndian, &opts).unwrap_err();
+        assert!(matches!(err.inner, err::ReadError::ByteLimitExceeded(1024)));
+        assert_eq!(err.path.to_string(), "[254]");
+    }
+
+    #[test]
+    fn test_read_from_slice() {
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_long_array("longs", vec![1, -2, i64::MAX])
+                .build(),
+        );
+        let mut buf = vec![];
+        nbt.write(&mut buf, &LittleEndian).unwrap();
+        buf.push(0xff);
+        assert_eq!(NBTTag::read_from_slice(&buf, &LittleEndian).unwrap(), nbt);
+        assert_eq!(NBTTag::read_from_mmap(&buf, &LittleEndian).unwrap(), nbt);
+    }
+
+    #[test]
+    fn test_empty_document() {
+        let buf: Vec<u8> = vec![0x00];
+        let mut reader = buf.as_slice();
+        let err = NBTTag::read(&mut reader, &BigEndian).unwrap_err();
+        assert!(matches!(err.inner, err::ReadError::EmptyDocument));
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_read_optional() {
+        let buf: Vec<u8> = vec![0x00, 0x01, 0x00, 0x00, 0x05];
+        let mut reader = buf.as_slice();
+        assert_eq!(
+            NBTTag::read_optional(&mut reader, &BigEndian).unwrap(),
+            None
+        );
+        assert_eq!(
+            NBTTag::read_optional(&mut reader, &BigEndian).unwrap(),
+            Some(NBTTag::from(5i8))
+        );
+        assert!(reader.is_empty());
+        assert!(NBTTag::read_optional(&mut reader, &BigEndian).is_err());
+
+        let nbt = NBTTag::from(5i8);
+        let mut written = vec![];
+        NBTTag::write_optional(None, &mut written, &BigEndian).unwrap();
+        NBTTag::write_optional(Some(&nbt), &mut written, &BigEndian).unwrap();
+        assert_eq!(written, buf);
+    }
+
+    #[test]
+    fn test_read_header() {
+        fn check(e: &(impl Reader + Writer)) {
+            let nbt = NBTTag::Compound(tag::Compound::builder().with_int("a", 1).build());
+            let opts = WriteOpts {
+                root_name: Some("root".to_string()),
+                ..Default::default()
+            };
+            let mut buf = vec![];
+            nbt.write_with(&mut buf, e, &opts).unwrap();
+            nbt.write(&mut buf, e).unwrap();
+
+            let mut reader = buf.as_slice();
+            let (tag_id, name) = NBTTag::read_header(&mut reader, e).unwrap();
+            assert_eq!((tag_id, name.as_str()), (tag::ids::COMPOUND, "root"));
+            let read =
+                NBTTag::read_payload_with(tag_id, &mut reader, e, &Default::default()).unwrap();
+            assert_eq!(read, nbt);
+            assert_eq!(NBTTag::read(&mut reader, e).unwrap(), nbt);
+        }
+        check(&BigEndian);
+        check(&LittleEndian);
+        check(&NetworkLittleEndian);
+
+        let mut reader: &[u8] = &[0x00, 0x01];
+        let header = NBTTag::read_header(&mut reader, &BigEndian).unwrap();
+        assert_eq!(header, (tag::ids::END, String::new()));
+        assert_eq!(reader, [0x01]);
+    }
+
+    #[test]
+    fn test_read_with_name() {
+        fn check(e: &(impl Reader + Writer)) {
+            let nbt = NBTTag::Compound(tag::Compound::builder().with_int("a", 1).build());
+            let mut buf = vec![];
+            nbt.write_with_name("Data", &mut buf, e).unwrap();
+            let (name, read) = NBTTag::read_with_name(&mut buf.as_slice(), e).unwrap();
+            assert_eq!((name.as_str(), read), ("Data", nbt.clone()));
+
+            buf.clear();
+            nbt.write(&mut buf, e).unwrap();
+            let (name, _) = NBTTag::read_with_name(&mut buf.as_slice(), e).unwrap();
+            assert_eq!(name, "");
+        }
+        check(&BigEndian);
+        check(&LittleEndian);
+        check(&NetworkLittleEndian);
+    }
+
+    #[test]
+    fn test_canonicalize_nan() {
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_float("float", f32::from_bits(0x7fc0_1234))
+                .with_double("double", f64::from_bits(0xfff8_0000_0000_5678))
+                .build(),
+        );
+        let mut buf = vec![];
+        nbt.write(&mut buf, &BigEndian).unwrap();
+
+        let bits = |nbt: &NBTTag| {
+            let view = nbt.view();
+            (
+                view.at("float").float().unwrap().to_bits(),
+                view.at("double").double().unwrap().to_bits(),
+            )
+        };
+        let read = NBTTag::read(&mut buf.as_slice(), &BigEndian).unwrap();
+        assert_eq!(bits(&read), (0x7fc0_1234, 0xfff8_0000_0000_5678));
+
+        let opts = ReadOpts {
+            canonicalize_nan: true,
+            ..Default::default()
+        };
+        let read = NBTTag::read_with(&mut buf.as_slice(), &BigEndian, &opts).unwrap();
+        assert_eq!(bits(&read), (f32::NAN.to_bits(), f64::NAN.to_bits()));
+    }
+
+    #[test]
+    fn test_unknown_arrays() {
+        // A compound containing a list of arrays with the unknown id 0x0d, of which each element
+        // is two bytes long.
+        let buf: Vec<u8> = vec![
+            0x0a, 0x00, 0x00, 0x09, 0x00, 0x01, 0x61, 0x0d, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+            0x00, 0x02, 0x12, 0x34, 0x56, 0x78, 0x00,
+        ];
+        let err = NBTTag::read(&mut buf.as_slice(), &BigEndian).unwrap_err();
+        assert!(matches!(err.inner, err::ReadError::UnknownTagType(0x0d)));
+
+        let opts = ReadOpts {
+            unknown_arrays: [(0x0d, 2)].into(),
+            ..Default::default()
+        };
+        let nbt = NBTTag::read_with(&mut buf.as_slice(), &BigEndian, &opts).unwrap();
+        let NBTTag::Compound(compound) = &nbt else {
+            panic!("expected a compound, found {nbt:?}");
+        };
+        assert_eq!(
+            compound["a"],
+            NBTTag::List(tag::List::from(vec![NBTTag::Unknown(tag::Unknown {
+                id: 0x0d,
+                len: 2,
+                bytes: vec![0x12, 0x34, 0x56, 0x78],
+            })]))
+        );
+        assert_eq!(compound["a"].summary(), "List<Unknown>{1 element}");
+
+        let mut written = vec![];
+        nbt.write(&mut written, &BigEndian).unwrap();
+        assert_eq!(written, buf);
+
+        let short = &buf[..buf.len() - 3];
+        assert!(NBTTag::read_with(&mut &short[..], &BigEndian, &opts).is_err());
+
+        // Unknown arrays are skipped in the same way when they are not read.
+        let entries = NBTTag::read_shallow_with(&mut buf.as_slice(), &BigEndian, &opts).unwrap();
+        assert_eq!(entries, vec![("a".to_string(), NBTTagType::List)]);
+        let opts = ReadOpts {
+            max_depth: Some(1),
+            depth_policy: DepthPolicy::Truncate,
+            ..opts
+        };
+        let mut reader = buf.as_slice();
+        let truncated = NBTTag::read_with(&mut reader, &BigEndian, &opts).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(truncated, nbt!({ "a": [] }));
+        assert!(NBTTag::read_with(&mut &short[..], &BigEndian, &opts).is_err());
+    }
+
     #[test]
     fn test_modified_utf8() {
         let normal_string = vec![0x08, 0x00, 0x00, 0x00, 0x04, 0x6e, 0x61, 0x6d, 0x65];
-        let nbt = NBTTag::read(&mut normal_string.as_slice(), &mut BigEndian).unwrap();
+        let nbt = NBTTag::read(&mut normal_string.as_slice(), &BigEndian).unwrap();
         assert!(matches!(&nbt, NBTTag::String(tag::String::Utf8(x)) if x == "name"));
         let mut buf = vec![];
-        nbt.write(&mut buf, &mut BigEndian).unwrap();
+        nbt.write(&mut buf, &BigEndian).unwrap();
         assert_eq!(normal_string, buf);
 
         let null_encoded_string = vec![0x08, 0x00, 0x00, 0x00, 0x04, 0xc0, 0x80, 0xc0, 0x80];
-        let nbt = NBTTag::read(&mut null_encoded_string.as_slice(), &mut BigEndian).unwrap();
+        let nbt = NBTTag::read(&mut null_encoded_string.as_slice(), &BigEndian).unwrap();
         assert!(matches!(&nbt, NBTTag::String(tag::String::Utf8(x)) if x == "\0\0"));
         let mut buf = vec![];
-        nbt.write(&mut buf, &mut BigEndian).unwrap();
+        nbt.write(&mut buf, &BigEndian).unwrap();
         assert_eq!(null_encoded_string, buf);
 
         let null_invalid_string = vec![0x08, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x80];
-        let nbt = NBTTag::read(&mut null_invalid_string.as_slice(), &mut BigEndian).unwrap();
+        let nbt = NBTTag::read(&mut null_invalid_string.as_slice(), &BigEndian).unwrap();
         assert!(matches!(
             &nbt,
             NBTTag::String(tag::String::Bytes(x)) if matches!(x.as_slice(), [0x00, 0x00, 0x00, 0x80])
         ));
         let mut buf = vec![];
-        nbt.write(&mut buf, &mut BigEndian).unwrap();
+        nbt.write(&mut buf, &BigEndian).unwrap();
         assert_eq!(null_invalid_string, buf);
     }
 }
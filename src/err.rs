@@ -1,13 +1,17 @@
 //! See [NbtError].
 use std::collections::VecDeque;
 use std::error::Error;
-use std::fmt::{Debug, Display, Formatter};
+use std::fmt::{Debug, Display, Formatter, Write};
+use std::str::FromStr;
 use thiserror::Error;
 
 use crate::NBTTagType;
 
 /// An error that can occur while reading NBT data from a buffer.
+///
+/// More variants may be added in the future, so this enum is non-exhaustive.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum ReadError {
     /// Occurs when an IO error occurs.
     #[error("{0}")]
@@ -26,13 +30,33 @@ pub enum ReadError {
     /// A byte sequence could not be read as a valid UTF-8 byte sequence.
     #[error("could not decode string")]
     InvalidString(Vec<u8>),
+    /// The compound and list tags in the buffer are nested deeper than the maximum depth.
+    #[error("nesting depth exceeds the maximum of {0}")]
+    DepthLimitExceeded(usize),
+    /// The total number of elements in the data exceeds the maximum.
+    #[error("number of elements exceeds the maximum of {0}")]
+    ElementLimitExceeded(usize),
+    /// The total number of bytes in the data exceeds the maximum.
+    #[error("number of bytes exceeds the maximum of {0}")]
+    ByteLimitExceeded(usize),
+    /// The data did not consume all of the bytes it was expected to occupy. Contains the number
+    /// of bytes left over.
+    #[error("{0} trailing bytes were not consumed")]
+    TrailingBytes(usize),
+    /// The document consists of only an end tag at the root, which some tools write to represent
+    /// the absence of any data.
+    #[error("document is empty")]
+    EmptyDocument,
     /// A custom variant for errors other than the provided variants.
     #[error("{0}")]
     Custom(String),
 }
 
 /// An error that can occur while writing NBT data into a buffer.
+///
+/// More variants may be added in the future, so this enum is non-exhaustive.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum WriteError {
     /// Occurs when an IO error occurs.
     #[error("{0}")]
@@ -44,11 +68,186 @@ pub enum WriteError {
     /// type.
     #[error("sequence length must be between 0 and {0}, but got {1}")]
     SeqLengthViolation(usize, usize),
+    /// The encoded length of a compound key exceeds the maximum length of a string. The path of
+    /// the error points to the offending key.
+    #[error("compound key length must be at most {max}, but got {0}", max = i16::MAX)]
+    KeyTooLong(usize),
+    /// The tag type is not supported by the format being written, as configured using
+    /// [crate::encode::WriteOpts].
+    #[error("tag type `{0}` is not supported by the target format")]
+    UnsupportedTag(NBTTagType),
+    /// The data did not pass validation before being written.
+    #[error("{0}")]
+    Invalid(ValidateError),
     /// A custom variant for errors other than the provided variants.
     #[error("{0}")]
     Custom(String),
 }
 
+/// An error that can occur while validating NBT data using [crate::NBTTag::validate].
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum ValidateError {
+    /// A compound tag contains an empty key.
+    #[error("compound key is empty")]
+    EmptyKey,
+    /// A list tag contains an element with a different type than its first element.
+    #[error("expected tag {expected}, found {found}")]
+    MixedList {
+        /// The type of the first element in the list.
+        expected: NBTTagType,
+        /// The type of the offending element.
+        found: NBTTagType,
+    },
+    /// A string or compound key is longer than [i16::MAX] bytes when encoded. Contains the encoded
+    /// length.
+    #[error("string length must be at most {max}, but got {0}", max = i16::MAX)]
+    StringTooLong(usize),
+    /// A float or double tag is NaN or infinite.
+    #[error("floating point value is not finite")]
+    NonFiniteFloat,
+}
+
+/// An error that can occur while converting an NBT tag into a Rust value using
+/// [crate::convert::FromNbt].
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum ConvertError {
+    /// The tag is of a different type than the Rust value requires.
+    #[error("expected tag of type `{expected}`, found type `{found}`")]
+    MismatchedType {
+        /// The tag type required by the Rust value.
+        expected: NBTTagType,
+        /// The type of the tag that was found.
+        found: NBTTagType,
+    },
+    /// A list tag has a different number of elements than the fixed-size array it is converted
+    /// into.
+    #[error("expected a list of {expected} elements, found {found} elements")]
+    LengthMismatch {
+        /// The length of the array.
+        expected: usize,
+        /// The length of the list tag.
+        found: usize,
+    },
+}
+
+/// An error that can occur while converting typed JSON into an NBT tag using
+/// [crate::NBTTag::from_typed_json].
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum TypedJsonError {
+    /// A JSON value representing a tag is not an object.
+    #[error("expected an object")]
+    ExpectedObject,
+    /// A JSON object representing a tag does not contain the named field.
+    #[error("missing field `{0}`")]
+    MissingField(&'static str),
+    /// The type of a tag is not one of the known type names.
+    #[error("unknown tag type `{0}`")]
+    UnknownType(String),
+    /// The JSON value of a tag is not valid for its declared type, for example because a number
+    /// is out of range for the type.
+    #[error("invalid value for a tag of type `{0}`")]
+    InvalidValue(NBTTagType),
+}
+
+/// An error that can occur while applying a [crate::diff::Difference] using
+/// [crate::NBTTag::apply_patch].
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum PatchError {
+    /// The tag that is changed or removed by the difference, or the parent of the tag that is
+    /// added, does not exist.
+    #[error("no tag exists at the path")]
+    MissingTag,
+    /// The difference adds a compound entry that already exists.
+    #[error("a tag already exists at the path")]
+    AlreadyExists,
+    /// The path of the difference is empty, or cannot be applied to the tags it refers to, such
+    /// as a list index applied to a compound tag.
+    #[error("the path cannot be applied to the tag")]
+    InvalidPath,
+}
+
+/// An error that can occur while parsing SNBT using [crate::NBTTag::from_snbt]. Each variant
+/// contains the byte offset in the input at which the error was found.
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum SnbtError {
+    /// The input ended before the value was complete.
+    #[error("unexpected end of input at offset {0}")]
+    UnexpectedEnd(usize),
+    /// A character was found that is not valid at its position.
+    #[error("expected {expected} at offset {offset}")]
+    Expected {
+        /// The byte offset of the unexpected character.
+        offset: usize,
+        /// A description of what was expected instead.
+        expected: &'static str,
+    },
+    /// A number is out of range for its type, such as `300b`.
+    #[error("number out of range at offset {0}")]
+    InvalidNumber(usize),
+    /// A quoted string contains an escape sequence that is not supported.
+    #[error("invalid escape sequence at offset {0}")]
+    InvalidEscape(usize),
+    /// A list contains an element of a different type than its first element.
+    #[error("list element of a different type at offset {0}")]
+    MixedList(usize),
+    /// An array contains an element that is not of the element type of the array, such as an int
+    /// in a byte array.
+    #[error("array element of the wrong type at offset {0}")]
+    InvalidArrayElement(usize),
+    /// Compound and list tags are nested deeper than the maximum depth of 512.
+    #[error("maximum nesting depth exceeded at offset {0}")]
+    DepthLimitExceeded(usize),
+    /// The value is followed by more input that is not whitespace.
+    #[error("unexpected trailing input at offset {0}")]
+    TrailingInput(usize),
+}
+
+impl SnbtError {
+    /// Returns the byte offset in the input at which the error was found.
+    pub fn offset(&self) -> usize {
+        match self {
+            SnbtError::UnexpectedEnd(offset)
+            | SnbtError::Expected { offset, .. }
+            | SnbtError::InvalidNumber(offset)
+            | SnbtError::InvalidEscape(offset)
+            | SnbtError::MixedList(offset)
+            | SnbtError::InvalidArrayElement(offset)
+            | SnbtError::DepthLimitExceeded(offset)
+            | SnbtError::TrailingInput(offset) => *offset,
+        }
+    }
+}
+
+/// An error that can occur while parsing a textual [Path].
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum PathError {
+    /// The path is not valid path syntax. Contains the byte offset at which the error was found.
+    #[error("invalid path syntax at offset {0}")]
+    InvalidSyntax(usize),
+    /// No tag exists for a part of the path. Contains the position of the part in the path.
+    #[error("no tag exists for path part {0}")]
+    MissingTag(usize),
+    /// A list index in the path is not smaller than the length of the list.
+    #[error("index {index} of path part {part} is out of bounds for a list of length {len}")]
+    IndexOutOfBounds {
+        /// The position of the part in the path.
+        part: usize,
+        /// The list index used by the part.
+        index: usize,
+        /// The length of the list.
+        len: usize,
+    },
+    /// A part of the path cannot be applied to the tag it refers to, such as a list index applied
+    /// to a compound tag or any part applied to a scalar tag.
+    #[error("path part {part} cannot be applied to a tag of type `{found}`")]
+    TypeMismatch {
+        /// The position of the part in the path.
+        part: usize,
+        /// The type of the tag that the part was applied to.
+        found: NBTTagType,
+    },
+}
+
 /// A generic wrapper that gives a [Path] to an error type.
 pub struct ErrorPath<I> {
     /// The inner element that the wrapper wraps around.
@@ -137,13 +336,141 @@ impl Path {
     pub fn from_single(part: PathPart) -> Self {
         Self(VecDeque::from([part]))
     }
+
+    /// Appends a [PathPart] to the end of the path.
+    pub fn push_back(&mut self, part: PathPart) {
+        self.0.push_back(part);
+    }
+
+    /// Appends all parts of another path to the end of this path.
+    pub fn join(mut self, other: Path) -> Self {
+        self.0.extend(other.0);
+        self
+    }
+}
+
+impl FromIterator<PathPart> for Path {
+    fn from_iter<T: IntoIterator<Item = PathPart>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
 }
 
+/// Parses a path such as `Inventory[0].tag.display.Name`.
+///
+/// Compound keys are separated by `.`, and list indices are written as `[n]`. A `\` escapes the
+/// next character, so keys that contain a `.`, `[` or `\` can be written as `foo\.bar`,
+/// `foo\[0]` and `foo\\bar` respectively. Empty keys are not allowed.
+///
+/// Keys can also be written in bracket notation as `["key"]`, in which a `\` only needs to be
+/// used to escape `"` and `\`. This is the notation written by the alternate [Display] format.
+impl FromStr for Path {
+    type Err = PathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut path = Path::default();
+        let mut chars = s.char_indices().peekable();
+        let mut expect_key = true;
+        while let Some(&(offset, c)) = chars.peek() {
+            match c {
+                '[' if !expect_key || path.0.is_empty() => {
+                    chars.next();
+                    if let Some((_, '"')) = chars.peek() {
+                        chars.next();
+                        let mut key = String::new();
+                        loop {
+                            match chars.next() {
+                                Some((_, '"')) => break,
+                                Some((offset, '\\')) => match chars.next() {
+                                    Some((_, c)) => key.push(c),
+                                    None => return Err(PathError::InvalidSyntax(offset)),
+                                },
+                                Some((_, c)) => key.push(c),
+                                None => return Err(PathError::InvalidSyntax(s.len())),
+                            }
+                        }
+                        match chars.next() {
+                            Some((_, ']')) => {}
+                            Some((offset, _)) => return Err(PathError::InvalidSyntax(offset)),
+                            None => return Err(PathError::InvalidSyntax(s.len())),
+                        }
+                        path.0.push_back(PathPart::MapKey(key));
+                        expect_key = false;
+                        continue;
+                    }
+                    let mut index = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, ']')) if !index.is_empty() => break,
+                            Some((_, c)) if c.is_ascii_digit() => index.push(c),
+                            Some((offset, _)) => return Err(PathError::InvalidSyntax(offset)),
+                            None => return Err(PathError::InvalidSyntax(s.len())),
+                        }
+                    }
+                    let index = index
+                        .parse()
+                        .map_err(|_| PathError::InvalidSyntax(offset))?;
+                    path.0.push_back(PathPart::Element(index));
+                    expect_key = false;
+                }
+                '.' if !expect_key => {
+                    chars.next();
+                    expect_key = true;
+                }
+                _ if expect_key => {
+                    let mut key = String::new();
+                    while let Some(&(offset, c)) = chars.peek() {
+                        match c {
+                            '.' | '[' => break,
+                            '\\' => {
+                                chars.next();
+                                match chars.next() {
+                                    Some((_, c)) => key.push(c),
+                                    None => return Err(PathError::InvalidSyntax(offset)),
+                                }
+                            }
+                            c => {
+                                chars.next();
+                                key.push(c);
+                            }
+                        }
+                    }
+                    if key.is_empty() {
+                        return Err(PathError::InvalidSyntax(offset));
+                    }
+                    path.0.push_back(PathPart::MapKey(key));
+                    expect_key = false;
+                }
+                _ => return Err(PathError::InvalidSyntax(offset)),
+            }
+        }
+        if expect_key {
+            return Err(PathError::InvalidSyntax(s.len()));
+        }
+        Ok(path)
+    }
+}
+
+/// Writes the path in a human-readable form such as `Inventory[0].tag.Name`, or `(root)` if the
+/// path is empty.
+///
+/// Keys are written as they are, which is ambiguous if they contain a `.` or `[`. The alternate
+/// format (`{:#}`) writes all keys in bracket notation instead, such as
+/// `["Inventory"][0]["tag"]["Name"]`, which can always be parsed back into the same path.
 impl Display for Path {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         if self.0.is_empty() {
             return f.write_str("(root)");
         }
+        if f.alternate() {
+            for part in &self.0 {
+                match part {
+                    PathPart::MapKey(v) | PathPart::Field(v) => write_quoted_key(f, v)?,
+                    PathPart::TupleField(v) => write_quoted_key(f, &v.to_string())?,
+                    PathPart::Element(v) => write!(f, "[{v}]")?,
+                }
+            }
+            return Ok(());
+        }
 
         let mut iter = self.0.iter();
         // Unwrapping will never panic here to the 'is empty' check.
@@ -161,6 +488,18 @@ impl Display for Path {
     }
 }
 
+/// Writes a key in the `["key"]` bracket notation, escaping any `"` and `\` characters.
+fn write_quoted_key(f: &mut Formatter<'_>, key: &str) -> std::fmt::Result {
+    f.write_str("[\"")?;
+    for c in key.chars() {
+        if matches!(c, '"' | '\\') {
+            f.write_char('\\')?;
+        }
+        f.write_char(c)?;
+    }
+    f.write_str("\"]")
+}
+
 /// A single part of an [Path].
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum PathPart {
@@ -188,3 +527,69 @@ impl Display for PathPart {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::err::{Path, PathError, PathPart};
+
+    #[test]
+    fn test_parse_path() {
+        let path: Path = r"display.Lore[1]".parse().unwrap();
+        assert_eq!(
+            Vec::from(path.0),
+            vec![
+                PathPart::MapKey("display".to_string()),
+                PathPart::MapKey("Lore".to_string()),
+                PathPart::Element(1),
+            ]
+        );
+
+        let path: Path = r"mod\.data.a\[b\\".parse().unwrap();
+        assert_eq!(
+            Vec::from(path.0),
+            vec![
+                PathPart::MapKey("mod.data".to_string()),
+                PathPart::MapKey(r"a[b\".to_string()),
+            ]
+        );
+
+        assert_eq!("".parse::<Path>(), Err(PathError::InvalidSyntax(0)));
+        assert_eq!("a..b".parse::<Path>(), Err(PathError::InvalidSyntax(2)));
+        assert_eq!("a[x]".parse::<Path>(), Err(PathError::InvalidSyntax(2)));
+        assert_eq!("a.".parse::<Path>(), Err(PathError::InvalidSyntax(2)));
+    }
+
+    #[test]
+    fn test_build_path() {
+        let mut path: Path = [PathPart::MapKey("a".to_string())].into_iter().collect();
+        path.push_back(PathPart::Element(1));
+        let path = path.join(Path::from_single(PathPart::MapKey("b".to_string())));
+        assert_eq!(path, "a[1].b".parse().unwrap());
+        assert_eq!(path.to_string(), "a[1].b");
+    }
+
+    #[test]
+    fn test_path_alternate_display() {
+        let path: Path = [
+            PathPart::MapKey("mod.data".to_string()),
+            PathPart::Element(0),
+            PathPart::MapKey(r#"say "hi" \ [x]"#.to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let alternate = format!("{path:#}");
+        assert_eq!(alternate, r#"["mod.data"][0]["say \"hi\" \\ [x]"]"#);
+        assert_eq!(alternate.parse::<Path>().unwrap(), path);
+        assert_eq!(format!("{:#}", Path::default()), "(root)");
+
+        assert_eq!(
+            r#"a["b.c"].d[1]"#.parse::<Path>().unwrap().to_string(),
+            "a.b.c.d[1]"
+        );
+        assert_eq!(
+            r#"a["b"x"#.parse::<Path>(),
+            Err(PathError::InvalidSyntax(5))
+        );
+        assert_eq!(r#"a["b"#.parse::<Path>(), Err(PathError::InvalidSyntax(4)));
+    }
+}
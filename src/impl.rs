@@ -1,12 +1,18 @@
 //! Implementations for type conversions from and to [NBTTag] using [From] and [TryFrom] and other
 //! useful traits and methods.
-use crate::decode::Reader;
-use crate::encode::Writer;
+use crate::decode::{ReadCtx, ReadOpts, Reader};
+use crate::encode::{WriteOpts, Writer};
 use crate::err::{ErrorPath, ReadError};
+use crate::tag::ids;
 use crate::{decode, encode, tag, NBTTag, NBTTagType, TagIo};
+use indexmap::IndexMap;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::io::{Read, Write};
-use std::ops::{Deref, DerefMut};
+use std::ops::{
+    Deref, DerefMut, Index, IndexMut, Range, RangeBounds, RangeFrom, RangeFull, RangeInclusive,
+    RangeTo, RangeToInclusive,
+};
 
 macro_rules! impl_enum_conv {
     ($typ:ty, $enum_variant:path) => {
@@ -27,6 +33,24 @@ macro_rules! impl_enum_conv {
                 $enum_variant(value.into())
             }
         }
+
+        impl PartialEq<$typ> for NBTTag {
+            fn eq(&self, other: &$typ) -> bool {
+                matches!(self, $enum_variant(v) if v == other)
+            }
+        }
+
+        impl PartialEq<$typ> for &NBTTag {
+            fn eq(&self, other: &$typ) -> bool {
+                **self == *other
+            }
+        }
+
+        impl PartialEq<NBTTag> for $typ {
+            fn eq(&self, other: &NBTTag) -> bool {
+                other == self
+            }
+        }
     };
     ($(($typ:ty, $enum_variant:path)$(,)?)*) => {
         $(impl_enum_conv!($typ, $enum_variant);)*
@@ -48,6 +72,52 @@ impl_enum_conv!(
     (tag::LongArray, NBTTag::LongArray),
 );
 
+macro_rules! impl_primitive_conv {
+    ($typ:ty, $newtyp:path, $enum_variant:path) => {
+        impl From<$typ> for NBTTag {
+            fn from(value: $typ) -> Self {
+                $enum_variant($newtyp(value))
+            }
+        }
+
+        impl PartialEq<$typ> for $newtyp {
+            fn eq(&self, other: &$typ) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl PartialEq<$newtyp> for $typ {
+            fn eq(&self, other: &$newtyp) -> bool {
+                *self == other.0
+            }
+        }
+    };
+    ($(($typ:ty, $newtyp:path, $enum_variant:path)$(,)?)*) => {
+        $(impl_primitive_conv!($typ, $newtyp, $enum_variant);)*
+    };
+}
+
+impl_primitive_conv!(
+    (i8, tag::Byte, NBTTag::Byte),
+    (i16, tag::Short, NBTTag::Short),
+    (i32, tag::Int, NBTTag::Int),
+    (i64, tag::Long, NBTTag::Long),
+    (f32, tag::Float, NBTTag::Float),
+    (f64, tag::Double, NBTTag::Double),
+);
+
+impl From<&str> for NBTTag {
+    fn from(value: &str) -> Self {
+        NBTTag::String(value.into())
+    }
+}
+
+impl From<String> for NBTTag {
+    fn from(value: String) -> Self {
+        NBTTag::String(value.into())
+    }
+}
+
 macro_rules! impl_newtype_conv {
     ($typ:ty, $newtyp:path) => {
         impl From<$newtyp> for $typ {
@@ -89,12 +159,52 @@ impl_newtype_conv!(
     (i64, tag::Long),
     (f32, tag::Float),
     (f64, tag::Double),
-    (HashMap<String, NBTTag>, tag::Compound),
+    (IndexMap<String, NBTTag>, tag::Compound),
     (Vec<i8>, tag::ByteArray),
     (Vec<i32>, tag::IntArray),
     (Vec<i64>, tag::LongArray),
 );
 
+/// The entries of the compound are in the iteration order of the [HashMap], which is unspecified.
+impl From<HashMap<String, NBTTag>> for tag::Compound {
+    fn from(value: HashMap<String, NBTTag>) -> Self {
+        tag::Compound(value.into_iter().collect())
+    }
+}
+
+/// The order of the entries of the compound is lost.
+impl From<tag::Compound> for HashMap<String, NBTTag> {
+    fn from(value: tag::Compound) -> Self {
+        value.0.into_iter().collect()
+    }
+}
+
+/// Converting a slice to an array tag clones the elements into a new [Vec].
+macro_rules! impl_array_slice_conv {
+    ($(($typ:ty, $newtyp:path)$(,)?)*) => {
+        $(
+            impl From<&[$typ]> for $newtyp {
+                fn from(value: &[$typ]) -> Self {
+                    $newtyp(value.to_vec())
+                }
+            }
+        )*
+    };
+}
+
+impl_array_slice_conv!(
+    (i8, tag::ByteArray),
+    (i32, tag::IntArray),
+    (i64, tag::LongArray),
+);
+
+/// Unsigned bytes are stored with the same bits as signed bytes.
+impl From<&[u8]> for tag::ByteArray {
+    fn from(value: &[u8]) -> Self {
+        tag::ByteArray(value.iter().map(|b| *b as i8).collect())
+    }
+}
+
 /// Special case: converting `&str` to a [tag::String] requires a clone.
 impl From<&str> for tag::String {
     fn from(value: &str) -> Self {
@@ -108,9 +218,232 @@ impl From<String> for tag::String {
     }
 }
 
+impl tag::String {
+    /// Returns the string as UTF-8, replacing any bytes that are not valid UTF-8 with the
+    /// replacement character.
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        match self {
+            tag::String::Utf8(s) => Cow::Borrowed(s),
+            tag::String::Bytes(b) => String::from_utf8_lossy(b),
+        }
+    }
+
+    /// Parses the string as a JSON value, such as the JSON text components used for item names
+    /// and lore.
+    ///
+    /// Returns [None] if the string is not valid JSON, or if it is not valid UTF-8.
+    #[cfg(feature = "json")]
+    pub fn as_json_component(&self) -> Option<serde_json::Value> {
+        match self {
+            tag::String::Utf8(s) => serde_json::from_str(s).ok(),
+            tag::String::Bytes(_) => None,
+        }
+    }
+}
+
+impl tag::List {
+    /// Returns the type of the elements in the list, which is the type that is written as the
+    /// content type of the list.
+    ///
+    /// For an empty list that was read, this is the content type it was read with, as long as that
+    /// is the id of a known tag type. Returns [None] for other empty lists, including those that
+    /// were read with the id of an end tag, as Minecraft: Java Edition writes empty lists.
+    pub fn declared_type(&self) -> Option<NBTTagType> {
+        match self.first() {
+            Some(v) => Some(v.tag_type()),
+            None => self.1.and_then(NBTTagType::from_id),
+        }
+    }
+
+    /// Returns the id of the content type that is written in the header of the list, which is the
+    /// id of the first element. For an empty list, this is the id of the content type it was read
+    /// with, or the id of a byte tag if it was not read.
+    ///
+    /// This allows the header of the list to be written manually, for example when embedding the
+    /// elements of a list in a custom container.
+    pub fn content_type_id(&self) -> u8 {
+        self.first()
+            .map_or(self.1.unwrap_or(ids::BYTE), NBTTag::tag_id)
+    }
+
+    /// Returns the index of the first element that is of a different type than the first element,
+    /// along with the type of the first element and the type of the offending element, or [None]
+    /// if all elements are of the same type.
+    ///
+    /// A list with such an element cannot be written, and writing it fails with an error at the
+    /// same index.
+    pub fn find_type_mismatch(&self) -> Option<(usize, NBTTagType, NBTTagType)> {
+        let first = self.first()?.tag_type();
+        self.iter()
+            .enumerate()
+            .find(|(_, v)| v.tag_type() != first)
+            .map(|(i, v)| (i, first, v.tag_type()))
+    }
+
+    /// Removes consecutive elements that are equal to each other, keeping only the first of each
+    /// run. This is the same as [Vec::dedup].
+    pub fn dedup(&mut self) {
+        self.0.dedup();
+    }
+
+    /// Removes all elements that are equal to an earlier element, keeping the first occurrence of
+    /// each distinct element in its original position.
+    ///
+    /// Since [NBTTag] cannot be hashed, every element is compared with all elements that are kept,
+    /// which takes quadratic time in the worst case.
+    pub fn dedup_all(&mut self) {
+        let mut kept: Vec<NBTTag> = Vec::with_capacity(self.len());
+        for v in self.0.drain(..) {
+            if !kept.contains(&v) {
+                kept.push(v);
+            }
+        }
+        self.0 = kept;
+    }
+}
+
+macro_rules! impl_list_as_vec {
+    ($(($fn_name:ident, $typ:ty, $variant:ident)$(,)?)*) => {
+        impl tag::List {
+            $(
+                #[doc = concat!("Returns the values of the elements as a `Vec<", stringify!($typ), ">` if every")]
+                #[doc = concat!("element is a [NBTTag::", stringify!($variant), "] tag, or [None] otherwise.")]
+                ///
+                /// An empty list always results in an empty vector.
+                pub fn $fn_name(&self) -> Option<Vec<$typ>> {
+                    self.0
+                        .iter()
+                        .map(|v| match v {
+                            NBTTag::$variant(v) => Some(v.0),
+                            _ => None,
+                        })
+                        .collect()
+                }
+            )*
+        }
+    };
+}
+
+impl_list_as_vec!(
+    (as_i8_vec, i8, Byte),
+    (as_i16_vec, i16, Short),
+    (as_i32_vec, i32, Int),
+    (as_i64_vec, i64, Long),
+    (as_f32_vec, f32, Float),
+    (as_f64_vec, f64, Double),
+);
+
+impl tag::List {
+    /// Returns the values of the elements as a `Vec<&str>` if every element is a [NBTTag::String]
+    /// tag that is valid UTF-8, or [None] otherwise.
+    ///
+    /// An empty list always results in an empty vector.
+    pub fn as_str_vec(&self) -> Option<Vec<&str>> {
+        self.0
+            .iter()
+            .map(|v| match v {
+                NBTTag::String(tag::String::Utf8(v)) => Some(v.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Creates a list of compound tags, such as the items of an inventory.
+    pub fn from_compounds(compounds: impl IntoIterator<Item = tag::Compound>) -> Self {
+        compounds
+            .into_iter()
+            .map(NBTTag::Compound)
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// Iterates over the elements of the list that are compound tags, skipping any other
+    /// elements.
+    pub fn compounds(&self) -> impl Iterator<Item = &tag::Compound> {
+        self.0.iter().filter_map(|v| match v {
+            NBTTag::Compound(v) => Some(v),
+            _ => None,
+        })
+    }
+}
+
+impl tag::ByteArray {
+    /// Iterates over the bytes in the array, reinterpreted as unsigned bytes.
+    pub fn iter_u8(&self) -> impl Iterator<Item = u8> + '_ {
+        self.0.iter().map(|b| *b as u8)
+    }
+
+    /// Appends an unsigned byte to the array, which is stored with the same bits as a signed byte.
+    pub fn push_u8(&mut self, value: u8) {
+        self.0.push(value as i8);
+    }
+
+    /// Appends all unsigned bytes in the slice to the array, which are stored with the same bits as
+    /// signed bytes.
+    pub fn extend_from_u8_slice(&mut self, values: &[u8]) {
+        self.0.extend(values.iter().map(|b| *b as i8));
+    }
+}
+
+macro_rules! impl_array_slice {
+    ($($newtyp:path$(,)?)*) => {
+        $(
+            impl $newtyp {
+                /// Clones the elements in the range into a new array tag.
+                ///
+                /// Panics if the range is out of bounds, in the same way as indexing a slice.
+                pub fn slice(&self, range: impl RangeBounds<usize>) -> $newtyp {
+                    $newtyp(self.0[(range.start_bound().cloned(), range.end_bound().cloned())].to_vec())
+                }
+            }
+        )*
+    };
+}
+
+impl_array_slice!(tag::ByteArray, tag::IntArray, tag::LongArray);
+
+impl tag::Float {
+    /// Returns true if both floats have the exact same bit pattern.
+    ///
+    /// Unlike [PartialEq], this considers `-0.0` and `0.0` to be different, and considers NaN
+    /// values to be equal to themselves.
+    pub fn bits_eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl tag::Double {
+    /// Returns true if both doubles have the exact same bit pattern.
+    ///
+    /// Unlike [PartialEq], this considers `-0.0` and `0.0` to be different, and considers NaN
+    /// values to be equal to themselves.
+    pub fn bits_eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
 impl<T: Into<NBTTag>> From<Vec<T>> for tag::List {
     fn from(value: Vec<T>) -> Self {
-        tag::List(value.into_iter().map(|v| v.into()).collect())
+        tag::List(value.into_iter().map(|v| v.into()).collect(), None)
+    }
+}
+
+impl<T: Into<NBTTag>, const N: usize> From<[T; N]> for tag::List {
+    fn from(value: [T; N]) -> Self {
+        tag::List(value.into_iter().map(|v| v.into()).collect(), None)
+    }
+}
+
+impl<K: Into<String>, V: Into<NBTTag>, const N: usize> From<[(K, V); N]> for tag::Compound {
+    /// Creates a compound tag from an array of key-value pairs. If a key occurs more than once,
+    /// the last value is kept.
+    fn from(value: [(K, V); N]) -> Self {
+        tag::Compound(
+            value
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
     }
 }
 
@@ -120,6 +453,12 @@ impl From<tag::List> for Vec<NBTTag> {
     }
 }
 
+impl PartialEq for tag::List {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
 impl Deref for tag::List {
     type Target = Vec<NBTTag>;
 
@@ -134,8 +473,69 @@ impl DerefMut for tag::List {
     }
 }
 
+impl Index<usize> for tag::List {
+    type Output = NBTTag;
+
+    /// Returns a reference to the element at the index.
+    ///
+    /// Panics with a message containing the index and the length of the list if the index is out
+    /// of bounds.
+    fn index(&self, index: usize) -> &Self::Output {
+        let len = self.0.len();
+        match self.0.get(index) {
+            Some(v) => v,
+            None => panic!("list index {index} out of range (len {len})"),
+        }
+    }
+}
+
+impl IndexMut<usize> for tag::List {
+    /// Returns a mutable reference to the element at the index.
+    ///
+    /// Panics with a message containing the index and the length of the list if the index is out
+    /// of bounds.
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        let len = self.0.len();
+        match self.0.get_mut(index) {
+            Some(v) => v,
+            None => panic!("list index {index} out of range (len {len})"),
+        }
+    }
+}
+
+/// Implements range indexing for [tag::List], which would otherwise no longer be available through
+/// [Deref] due to the custom [Index] implementation for `usize`.
+macro_rules! impl_list_range_index {
+    ($($range:ty$(,)?)*) => {
+        $(
+            impl Index<$range> for tag::List {
+                type Output = [NBTTag];
+
+                fn index(&self, index: $range) -> &Self::Output {
+                    &self.0[index]
+                }
+            }
+
+            impl IndexMut<$range> for tag::List {
+                fn index_mut(&mut self, index: $range) -> &mut Self::Output {
+                    &mut self.0[index]
+                }
+            }
+        )*
+    };
+}
+
+impl_list_range_index!(
+    Range<usize>,
+    RangeFrom<usize>,
+    RangeFull,
+    RangeInclusive<usize>,
+    RangeTo<usize>,
+    RangeToInclusive<usize>,
+);
+
 macro_rules! impl_tagtype {
-    ($typ:ty, $enum_variant:path, $variant_num:literal) => {
+    ($typ:ty, $enum_variant:path, $variant_num:expr) => {
         impl $typ {
             /// Returns the [NBTTagType] associated with this tag.
             #[inline]
@@ -148,7 +548,12 @@ macro_rules! impl_tagtype {
             /// Attempts to read the data from a buffer into an NBT value using the specified
             /// [Reader] encoding.
             ///
-            /// Returns an error if the variant byte doesn't match this tag type.
+            /// The data must start with the type and name of a root tag, just like when using
+            /// [NBTTag::read]. Use this to read documents where the root tag is expected to be of
+            /// this specific type, such as documents with a bare list at the root.
+            ///
+            /// Returns a [ReadError::UnexpectedTag] error if the variant byte doesn't match this
+            /// tag type.
             pub fn read(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
                 let tag_id = r.u8(buf)?;
                 if tag_id != $variant_num {
@@ -157,28 +562,236 @@ macro_rules! impl_tagtype {
                         tag_id,
                     )));
                 }
-                r.string(buf)?;
-                Self::read_payload(buf, r)
+                r.root_name(buf)?;
+                Self::read_payload(buf, r, &mut ReadCtx::new(&ReadOpts::default()))
             }
 
             /// Attempts to write the NBT data into a buffer using the specified [Writer] encoding.
             pub fn write(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
-                w.write_u8(buf, $variant_num)?;
-                w.write_string(buf, "")?;
-                self.write_payload(buf, w)
+                self.write_with(buf, w, &WriteOpts::default())
+            }
+
+            /// Attempts to write the NBT data into a buffer using the specified [Writer] encoding
+            /// and [WriteOpts].
+            pub fn write_with(
+                &self,
+                buf: &mut impl Write,
+                w: &impl Writer,
+                opts: &WriteOpts,
+            ) -> encode::Res {
+                opts.write_header(buf, w, $variant_num)?;
+                self.write_payload(buf, w, opts)
             }
         }
     };
 }
-impl_tagtype!(tag::Byte, NBTTagType::Byte, 1);
-impl_tagtype!(tag::Short, NBTTagType::Short, 2);
-impl_tagtype!(tag::Int, NBTTagType::Int, 3);
-impl_tagtype!(tag::Long, NBTTagType::Long, 4);
-impl_tagtype!(tag::Float, NBTTagType::Float, 5);
-impl_tagtype!(tag::Double, NBTTagType::Double, 6);
-impl_tagtype!(tag::String, NBTTagType::String, 8);
-impl_tagtype!(tag::Compound, NBTTagType::Compound, 10);
-impl_tagtype!(tag::List, NBTTagType::List, 9);
-impl_tagtype!(tag::ByteArray, NBTTagType::ByteArray, 7);
-impl_tagtype!(tag::IntArray, NBTTagType::IntArray, 11);
-impl_tagtype!(tag::LongArray, NBTTagType::LongArray, 12);
+impl_tagtype!(tag::Byte, NBTTagType::Byte, ids::BYTE);
+impl_tagtype!(tag::Short, NBTTagType::Short, ids::SHORT);
+impl_tagtype!(tag::Int, NBTTagType::Int, ids::INT);
+impl_tagtype!(tag::Long, NBTTagType::Long, ids::LONG);
+impl_tagtype!(tag::Float, NBTTagType::Float, ids::FLOAT);
+impl_tagtype!(tag::Double, NBTTagType::Double, ids::DOUBLE);
+impl_tagtype!(tag::String, NBTTagType::String, ids::STRING);
+impl_tagtype!(tag::Compound, NBTTagType::Compound, ids::COMPOUND);
+impl_tagtype!(tag::List, NBTTagType::List, ids::LIST);
+impl_tagtype!(tag::ByteArray, NBTTagType::ByteArray, ids::BYTE_ARRAY);
+impl_tagtype!(tag::IntArray, NBTTagType::IntArray, ids::INT_ARRAY);
+impl_tagtype!(tag::LongArray, NBTTagType::LongArray, ids::LONG_ARRAY);
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::BigEndian;
+    use crate::tag::ids;
+    use crate::{tag, NBTTag, NBTTagType};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_hash_map_conv() {
+        let compound = tag::Compound::from([("a", 1), ("b", 2)]);
+        let map = HashMap::from(compound.clone());
+        assert_eq!(map["b"], NBTTag::from(2));
+        assert_eq!(tag::Compound::from(map), compound);
+    }
+
+    #[test]
+    fn test_declared_type() {
+        let list = tag::List::from([tag::Int(1), tag::Int(2)]);
+        assert_eq!(list.declared_type(), Some(NBTTagType::Int));
+        assert_eq!(tag::List::default().declared_type(), None);
+
+        for id in [ids::INT, ids::END, 0x7f] {
+            let mut buf = vec![];
+            NBTTag::List(tag::List::default())
+                .write(&mut buf, &BigEndian)
+                .unwrap();
+            buf[3] = id;
+            let read = NBTTag::read(&mut buf.as_slice(), &BigEndian).unwrap();
+            let NBTTag::List(list) = &read else {
+                panic!("expected a list");
+            };
+            assert_eq!(list.content_type_id(), id);
+            assert_eq!(list.declared_type(), NBTTagType::from_id(id));
+            assert_eq!(read, NBTTag::List(tag::List::default()));
+
+            let mut written = vec![];
+            read.write(&mut written, &BigEndian).unwrap();
+            assert_eq!(written, buf);
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_as_json_component() {
+        let s = tag::String::from(r#"{"text":"Sword","color":"gold"}"#);
+        let json = s.as_json_component().unwrap();
+        assert_eq!(json["color"], "gold");
+
+        assert_eq!(tag::String::from("Sword").as_json_component(), None);
+        assert_eq!(tag::String::Bytes(vec![0xff]).as_json_component(), None);
+    }
+
+    #[test]
+    fn test_tag_eq() {
+        let compound = tag::Compound::builder().with_int("a", 5).build();
+        let nbt = compound.get("a").unwrap();
+        assert_eq!(nbt, tag::Int(5));
+        assert_eq!(tag::Int(5), *nbt);
+        assert_ne!(nbt, tag::Int(6));
+        assert_ne!(nbt, tag::Long(5));
+        assert_eq!(NBTTag::Compound(compound.clone()), compound);
+    }
+
+    #[test]
+    fn test_find_type_mismatch() {
+        let mut list = tag::List::from([1i32, 2]);
+        assert_eq!(list.find_type_mismatch(), None);
+        list.push(NBTTag::from("a"));
+        list.push(NBTTag::from(1i64));
+        assert_eq!(
+            list.find_type_mismatch(),
+            Some((2, NBTTagType::Int, NBTTagType::String))
+        );
+        assert_eq!(tag::List::default().find_type_mismatch(), None);
+    }
+
+    #[test]
+    fn test_list_dedup() {
+        let mut list = tag::List::from([1i32, 1, 2, 1, 2, 2]);
+        list.dedup();
+        assert_eq!(list, tag::List::from([1i32, 2, 1, 2]));
+        list.dedup_all();
+        assert_eq!(list, tag::List::from([1i32, 2]));
+    }
+
+    #[test]
+    fn test_list_compounds() {
+        let items = (0..3).map(|slot| {
+            tag::Compound::builder()
+                .with_byte("Slot", slot)
+                .with_string("id", "minecraft:stone")
+                .build()
+        });
+        let list = tag::List::from_compounds(items);
+        assert_eq!(list.len(), 3);
+        assert_eq!(
+            list.compounds()
+                .map(|v| v.get("Slot").cloned())
+                .collect::<Vec<_>>(),
+            [0i8, 1, 2].map(|v| Some(NBTTag::from(v)))
+        );
+        assert_eq!(tag::List::from([1i32, 2]).compounds().count(), 0);
+    }
+
+    #[test]
+    fn test_primitive_eq() {
+        assert_eq!(tag::Int(5), 5);
+        assert_eq!(5, tag::Int(5));
+        assert_ne!(tag::Byte(5), 6);
+        assert_eq!(tag::Double(0.5), 0.5);
+        assert_ne!(tag::Float(f32::NAN), f32::NAN);
+    }
+
+    #[test]
+    fn test_content_type_id() {
+        assert_eq!(tag::List::default().content_type_id(), ids::BYTE);
+        let list = tag::List::from(vec!["a", "b"]);
+        assert_eq!(list.content_type_id(), ids::STRING);
+
+        let mut buf = vec![];
+        list.write(&mut buf, &BigEndian).unwrap();
+        assert_eq!(buf[3], list.content_type_id());
+    }
+
+    #[test]
+    fn test_list_as_vec() {
+        let list = tag::List::from(vec![1i32, 2, 3]);
+        assert_eq!(list.as_i32_vec(), Some(vec![1, 2, 3]));
+        assert_eq!(list.as_i64_vec(), None);
+        assert_eq!(tag::List::from(vec![0.5f64]).as_f64_vec(), Some(vec![0.5]));
+        assert_eq!(
+            tag::List::from(vec!["a", "b"]).as_str_vec(),
+            Some(vec!["a", "b"])
+        );
+        assert_eq!(tag::List::default().as_i8_vec(), Some(vec![]));
+
+        let mixed = tag::List::from(vec![NBTTag::from(1i32), NBTTag::from(1i64)]);
+        assert_eq!(mixed.as_i32_vec(), None);
+    }
+
+    #[test]
+    fn test_byte_array_u8() {
+        let mut array = tag::ByteArray::default();
+        array.push_u8(255);
+        array.extend_from_u8_slice(&[0, 128]);
+        assert_eq!(array.0, [-1, 0, -128]);
+        assert_eq!(array.iter_u8().collect::<Vec<_>>(), [255, 0, 128]);
+    }
+
+    #[test]
+    fn test_array_from_slice() {
+        let bytes: &[u8] = &[0, 255];
+        assert_eq!(tag::ByteArray::from(bytes).0, [0, -1]);
+        assert_eq!(tag::ByteArray::from(&[1i8, -1][..]).0, [1, -1]);
+        assert_eq!(tag::IntArray::from(&[1, 2][..]).0, [1, 2]);
+        assert_eq!(tag::LongArray::from(&[i64::MAX][..]).0, [i64::MAX]);
+
+        let compound = tag::Compound::builder()
+            .with_byte_array("bytes", bytes)
+            .build();
+        assert_eq!(compound["bytes"], NBTTag::ByteArray(vec![0, -1].into()));
+    }
+
+    #[test]
+    fn test_array_slice() {
+        let array = tag::IntArray(vec![1, 2, 3, 4]);
+        assert_eq!(array.slice(1..3).0, [2, 3]);
+        assert_eq!(array.slice(..=1).0, [1, 2]);
+        assert_eq!(array.slice(2..).0, [3, 4]);
+        assert!(array.slice(4..).is_empty());
+        assert_eq!(tag::ByteArray(vec![1, -1]).slice(1..).0, [-1]);
+        assert_eq!(tag::LongArray(vec![1, 2]).slice(..).0, [1, 2]);
+    }
+
+    #[test]
+    fn test_from_array() {
+        let compound = tag::Compound::from([("a", 1i32), ("b", 2i32)]);
+        assert_eq!(
+            compound,
+            tag::Compound::builder()
+                .with_int("a", 1)
+                .with_int("b", 2)
+                .build()
+        );
+        let list = tag::List::from(["a", "b"]);
+        assert_eq!(list, tag::List::from(vec!["a", "b"]));
+    }
+
+    #[test]
+    #[should_panic(expected = "list index 5 out of range (len 3)")]
+    fn test_list_index_out_of_range() {
+        let list = tag::List::from([1i32, 2, 3]);
+        assert_eq!(list[1], NBTTag::Int(2.into()));
+        assert_eq!(list[1..].len(), 2);
+        let _ = &list[5];
+    }
+}
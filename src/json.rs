@@ -0,0 +1,299 @@
+//! Conversion of NBT data to and from a typed JSON representation, in which every value is tagged
+//! with its type. See [NBTTag::to_typed_json].
+use serde_json::{json, Number, Value};
+
+use crate::err::{ErrorPath, PathPart, TypedJsonError};
+use crate::{tag, NBTTag, NBTTagType};
+
+/// A short notation for the result type used by [NBTTag::from_typed_json].
+type Res<T> = Result<T, ErrorPath<TypedJsonError>>;
+
+impl NBTTag {
+    /// Converts the NBT tag into a JSON value in which every tag is represented as an object with
+    /// its type and value, such as `{"type":"int","value":5}`. Unlike a plain JSON mapping, this is
+    /// lossless, so the tag can be restored exactly using [Self::from_typed_json], including the
+    /// order of the entries in compound tags.
+    ///
+    /// The types are named `byte`, `short`, `int`, `long`, `float`, `double`, `string`,
+    /// `compound`, `list`, `byte_array`, `int_array` and `long_array`. The value of a compound is
+    /// an object of typed values, and the value of a list is an array of typed values. An empty list
+    /// that was read with a content type also has a `content_type` field with the id of that type.
+    ///
+    /// Floats and doubles that are not finite are written as the strings `NaN`, `Infinity` and
+    /// `-Infinity`. Since there are many different NaN values, NaN values also have a `bits` field
+    /// with the bits of the value as an unsigned integer.
+    ///
+    /// Strings that are not valid UTF-8 are written as an array of their bytes, and unknown tags
+    /// are written with the type `unknown`, their `id` and `len`, and an array of their bytes.
+    pub fn to_typed_json(&self) -> Value {
+        match self {
+            NBTTag::Float(v) if v.0.is_nan() => {
+                return json!({"type": "float", "value": "NaN", "bits": v.0.to_bits()});
+            }
+            NBTTag::Double(v) if v.0.is_nan() => {
+                return json!({"type": "double", "value": "NaN", "bits": v.0.to_bits()});
+            }
+            NBTTag::List(tag::List(v, Some(id))) if v.is_empty() => {
+                return json!({"type": "list", "value": [], "content_type": id});
+            }
+            _ => {}
+        }
+        let value = match self {
+            NBTTag::Byte(v) => json!(v.0),
+            NBTTag::Short(v) => json!(v.0),
+            NBTTag::Int(v) => json!(v.0),
+            NBTTag::Long(v) => json!(v.0),
+            NBTTag::Float(v) => float_to_json(v.0 as f64),
+            NBTTag::Double(v) => float_to_json(v.0),
+            NBTTag::String(tag::String::Utf8(v)) => json!(v),
+            NBTTag::String(tag::String::Bytes(v)) => json!(v),
+            NBTTag::Compound(v) => Value::Object(
+                v.iter()
+                    .map(|(k, v)| (k.clone(), v.to_typed_json()))
+                    .collect(),
+            ),
+            NBTTag::List(v) => Value::Array(v.iter().map(NBTTag::to_typed_json).collect()),
+            NBTTag::ByteArray(v) => json!(v.0),
+            NBTTag::IntArray(v) => json!(v.0),
+            NBTTag::LongArray(v) => json!(v.0),
+            NBTTag::Unknown(v) => {
+                return json!({"type": "unknown", "id": v.id, "len": v.len, "value": v.bytes});
+            }
+        };
+        json!({"type": type_name(self.tag_type()), "value": value})
+    }
+
+    /// Converts a JSON value in the typed representation written by [Self::to_typed_json] back
+    /// into an NBT tag.
+    ///
+    /// The `bits` field of a float or double takes precedence over its value, and must be the bits
+    /// of a NaN value.
+    ///
+    /// Returns a [TypedJsonError] with the path of the offending value if the JSON is not in the
+    /// typed representation, or if a value does not fit in its declared type.
+    pub fn from_typed_json(json: &Value) -> Res<Self> {
+        let obj = json
+            .as_object()
+            .ok_or_else(|| ErrorPath::new(TypedJsonError::ExpectedObject))?;
+        let type_name = obj
+            .get("type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ErrorPath::new(TypedJsonError::MissingField("type")))?;
+        let value = obj
+            .get("value")
+            .ok_or_else(|| ErrorPath::new(TypedJsonError::MissingField("value")))?;
+
+        Ok(match type_name {
+            "byte" => NBTTag::Byte(tag::Byte(int(value, NBTTagType::Byte)?)),
+            "short" => NBTTag::Short(tag::Short(int(value, NBTTagType::Short)?)),
+            "int" => NBTTag::Int(tag::Int(int(value, NBTTagType::Int)?)),
+            "long" => NBTTag::Long(tag::Long(int(value, NBTTagType::Long)?)),
+            "float" => NBTTag::Float(tag::Float(match obj.get("bits") {
+                Some(bits) => nan_bits(bits, f32::from_bits, NBTTagType::Float)?,
+                None => float(value, NBTTagType::Float)? as f32,
+            })),
+            "double" => NBTTag::Double(tag::Double(match obj.get("bits") {
+                Some(bits) => nan_bits(bits, f64::from_bits, NBTTagType::Double)?,
+                None => float(value, NBTTagType::Double)?,
+            })),
+            "string" => NBTTag::String(match value {
+                Value::String(v) => tag::String::Utf8(v.clone()),
+                _ => tag::String::Bytes(ints(value, NBTTagType::String)?),
+            }),
+            "compound" => {
+                let map = value
+                    .as_object()
+                    .ok_or_else(|| invalid(NBTTagType::Compound))?;
+                let mut compound = tag::Compound::default();
+                for (k, v) in map {
+                    let v = Self::from_typed_json(v)
+                        .map_err(|err| err.prepend(PathPart::MapKey(k.clone())))?;
+                    compound.insert(k.clone(), v);
+                }
+                NBTTag::Compound(compound)
+            }
+            "list" => NBTTag::List(tag::List(
+                value
+                    .as_array()
+                    .ok_or_else(|| invalid(NBTTagType::List))?
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        Self::from_typed_json(v).map_err(|err| err.prepend(PathPart::Element(i)))
+                    })
+                    .collect::<Res<_>>()?,
+                match obj.get("content_type") {
+                    Some(id) => Some(int(id, NBTTagType::List)?),
+                    None => None,
+                },
+            )),
+            "byte_array" => NBTTag::ByteArray(tag::ByteArray(ints(value, NBTTagType::ByteArray)?)),
+            "int_array" => NBTTag::IntArray(tag::IntArray(ints(value, NBTTagType::IntArray)?)),
+            "long_array" => NBTTag::LongArray(tag::LongArray(ints(value, NBTTagType::LongArray)?)),
+            "unknown" => NBTTag::Unknown(tag::Unknown {
+                id: int(obj.get("id").unwrap_or(&Value::Null), NBTTagType::Unknown)?,
+                len: int(obj.get("len").unwrap_or(&Value::Null), NBTTagType::Unknown)?,
+                bytes: ints(value, NBTTagType::Unknown)?,
+            }),
+            other => {
+                return Err(ErrorPath::new(TypedJsonError::UnknownType(
+                    other.to_string(),
+                )))
+            }
+        })
+    }
+}
+
+/// Returns the name used for a tag type in the typed JSON representation.
+fn type_name(tag_type: NBTTagType) -> &'static str {
+    match tag_type {
+        NBTTagType::Byte => "byte",
+        NBTTagType::Short => "short",
+        NBTTagType::Int => "int",
+        NBTTagType::Long => "long",
+        NBTTagType::Float => "float",
+        NBTTagType::Double => "double",
+        NBTTagType::String => "string",
+        NBTTagType::Compound => "compound",
+        NBTTagType::List => "list",
+        NBTTagType::ByteArray => "byte_array",
+        NBTTagType::IntArray => "int_array",
+        NBTTagType::LongArray => "long_array",
+        NBTTagType::Unknown => "unknown",
+    }
+}
+
+fn float_to_json(v: f64) -> Value {
+    match Number::from_f64(v) {
+        Some(n) => Value::Number(n),
+        None if v.is_nan() => json!("NaN"),
+        None if v > 0.0 => json!("Infinity"),
+        None => json!("-Infinity"),
+    }
+}
+
+fn invalid(tag_type: NBTTagType) -> ErrorPath<TypedJsonError> {
+    ErrorPath::new(TypedJsonError::InvalidValue(tag_type))
+}
+
+/// Reads an integer that must fit in `T` without losing any information.
+fn int<T: TryFrom<i64>>(value: &Value, tag_type: NBTTagType) -> Res<T> {
+    value
+        .as_i64()
+        .and_then(|v| T::try_from(v).ok())
+        .ok_or_else(|| invalid(tag_type))
+}
+
+fn ints<T: TryFrom<i64>>(value: &Value, tag_type: NBTTagType) -> Res<Vec<T>> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid(tag_type))?
+        .iter()
+        .map(|v| int(v, tag_type))
+        .collect()
+}
+
+/// Reads the bits of a NaN value, which must be an unsigned integer that fits in `B` and that is
+/// converted into a NaN value by `from_bits`.
+fn nan_bits<B: TryFrom<u64>, F: Copy + Into<f64>>(
+    bits: &Value,
+    from_bits: fn(B) -> F,
+    tag_type: NBTTagType,
+) -> Res<F> {
+    bits.as_u64()
+        .and_then(|v| B::try_from(v).ok())
+        .map(from_bits)
+        .filter(|v| (*v).into().is_nan())
+        .ok_or_else(|| invalid(tag_type))
+}
+
+fn float(value: &Value, tag_type: NBTTagType) -> Res<f64> {
+    match value {
+        Value::Number(n) => n.as_f64().ok_or_else(|| invalid(tag_type)),
+        Value::String(s) if s == "NaN" => Ok(f64::NAN),
+        Value::String(s) if s == "Infinity" => Ok(f64::INFINITY),
+        Value::String(s) if s == "-Infinity" => Ok(f64::NEG_INFINITY),
+        _ => Err(invalid(tag_type)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::err::TypedJsonError;
+    use crate::tag::ids;
+    use crate::{tag, NBTTag, NBTTagType};
+
+    #[test]
+    fn test_typed_json() {
+        let nbt = nbt!({
+            "byte": 1i8,
+            "long": i64::MAX,
+            "float": 0.1f32,
+            "nan": f64::NAN,
+            "payload": f32::from_bits(0x7fa0_0001),
+            "list": [[I; 1, -1], [I;]],
+            "bytes": [B; -1],
+        });
+        let json = nbt.to_typed_json();
+        assert_eq!(json["value"]["byte"], json!({"type": "byte", "value": 1}));
+        assert_eq!(
+            json["value"]["nan"],
+            json!({"type": "double", "value": "NaN", "bits": f64::NAN.to_bits()})
+        );
+
+        let read = NBTTag::from_typed_json(&json).unwrap();
+        // Compound equality ignores the order of entries, so check it separately.
+        assert!(read
+            .view()
+            .compound()
+            .unwrap()
+            .keys()
+            .eq(["byte", "long", "float", "nan", "payload", "list", "bytes"]));
+        // NaN is not equal to itself, so compare the typed JSON of both instead.
+        assert_eq!(read.to_typed_json(), json);
+        assert_eq!(
+            read.view().at("float").float().unwrap().to_bits(),
+            0.1f32.to_bits()
+        );
+        assert_eq!(
+            read.view().at("payload").float().unwrap().to_bits(),
+            0x7fa0_0001
+        );
+
+        let nbt = NBTTag::String(tag::String::Bytes(vec![0xff]));
+        assert_eq!(NBTTag::from_typed_json(&nbt.to_typed_json()).unwrap(), nbt);
+
+        let list = NBTTag::List(tag::List(vec![], Some(ids::INT)));
+        let json = list.to_typed_json();
+        assert_eq!(
+            json,
+            json!({"type": "list", "value": [], "content_type": ids::INT})
+        );
+        let NBTTag::List(read) = NBTTag::from_typed_json(&json).unwrap() else {
+            panic!("expected a list");
+        };
+        assert_eq!(read.declared_type(), Some(NBTTagType::Int));
+    }
+
+    #[test]
+    fn test_typed_json_error() {
+        let json = json!({"type": "list", "value": [
+            {"type": "compound", "value": {"a": {"type": "byte", "value": 128}}},
+        ]});
+        let err = NBTTag::from_typed_json(&json).unwrap_err();
+        assert_eq!(err.inner, TypedJsonError::InvalidValue(NBTTagType::Byte));
+        assert_eq!(err.path.to_string(), "[0].a");
+
+        let err = NBTTag::from_typed_json(&json!({"type": "uint", "value": 1})).unwrap_err();
+        assert_eq!(err.inner, TypedJsonError::UnknownType("uint".to_string()));
+        let err = NBTTag::from_typed_json(&json!({"type": "int"})).unwrap_err();
+        assert_eq!(err.inner, TypedJsonError::MissingField("value"));
+        let err = NBTTag::from_typed_json(&json!([1])).unwrap_err();
+        assert_eq!(err.inner, TypedJsonError::ExpectedObject);
+        let json = json!({"type": "double", "value": "NaN", "bits": 1});
+        let err = NBTTag::from_typed_json(&json).unwrap_err();
+        assert_eq!(err.inner, TypedJsonError::InvalidValue(NBTTagType::Double));
+    }
+}
@@ -1,30 +1,50 @@
 #![doc = include_str!("../README.md")]
 #![deny(missing_docs)]
 
-use std::collections::HashMap;
+use indexmap::IndexMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
+use std::str::FromStr;
 
 use strum_macros::{Display, IntoStaticStr};
 
-use encode::Writer;
+use encode::{WriteOpts, Writer};
 
-use crate::decode::Reader;
+use crate::decode::{LimitedRead, ReadCtx, ReadOpts, Reader, Warning, WarningReason};
 use crate::err::{ErrorPath, Path, PathPart, ReadError, WriteError};
+use crate::shared::SharedTag;
+use crate::tag::ids;
 use crate::view::View;
 
+#[macro_use]
+mod macros;
+
+#[cfg(feature = "bytes")]
+mod buf;
+mod compression;
+pub mod convert;
 pub mod decode;
+pub mod diff;
 pub mod encode;
 pub mod encoding;
 pub mod err;
 mod r#impl;
+#[cfg(feature = "json")]
+mod json;
 #[cfg(feature = "serde")]
 pub mod serde;
+pub mod shared;
+pub mod snbt;
 pub mod tag;
+pub mod validate;
 pub mod view;
 
 /// An enum representing all possible NBT data.
+///
+/// More tag types may be added in the future, so this enum is non-exhaustive.
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum NBTTag {
     /// An 8-bit signed integer.
     Byte(tag::Byte),
@@ -57,11 +77,21 @@ pub enum NBTTag {
     IntArray(tag::IntArray),
     /// A variable-length array containing 64-bit signed integers.
     LongArray(tag::LongArray),
+    /// A tag with an id that is not known to this crate, which is only read if it is enabled in
+    /// [ReadOpts::unknown_arrays].
+    Unknown(tag::Unknown),
 }
 
 /// An enum representing all possible NBT tag types.
+///
+/// Can be parsed from a string using [std::str::FromStr], which accepts both the variant names
+/// (such as `Compound`) and the names used by Minecraft (such as `TAG_Compound`).
+/// [NBTTagType::Unknown] cannot be parsed, as it does not refer to a single tag type.
+///
+/// More tag types may be added in the future, so this enum is non-exhaustive.
 #[allow(missing_docs)]
 #[derive(Copy, Clone, Debug, Display, IntoStaticStr, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum NBTTagType {
     Byte,
     Short,
@@ -75,6 +105,41 @@ pub enum NBTTagType {
     ByteArray,
     IntArray,
     LongArray,
+    Unknown,
+}
+
+/// Determines in which direction [NBTTag::normalize_arrays] converts between lists and arrays.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ArrayDirection {
+    /// Convert non-empty lists that only contain byte, int or long tags into the corresponding
+    /// array tag.
+    ToArrays,
+    /// Convert byte, int and long array tags into lists of the corresponding scalar tag.
+    ToLists,
+}
+
+/// Options that determine how [NBTTag::canonicalize] transforms a tag.
+#[derive(Debug, Default, Clone)]
+pub struct CanonOpts {
+    /// The direction in which lists and arrays are converted using [NBTTag::normalize_arrays], or
+    /// `None` to leave lists and arrays as they are.
+    pub normalize_arrays: Option<ArrayDirection>,
+}
+
+/// The structure of an [NBTTag] without any of its values, as returned by [NBTTag::type_tree].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeNode {
+    /// A tag that is not a compound or list tag, including array tags.
+    Scalar(NBTTagType),
+    /// A compound tag, mapping each of its keys to the structure of the corresponding value.
+    Compound(IndexMap<String, TypeNode>),
+    /// A list tag.
+    List {
+        /// The structure of the first element of the list, or [None] if the list is empty.
+        element: Option<Box<TypeNode>>,
+        /// The number of elements in the list.
+        len: usize,
+    },
 }
 
 impl NBTTag {
@@ -93,166 +158,1083 @@ impl NBTTag {
             NBTTag::ByteArray(v) => v.tag_type(),
             NBTTag::IntArray(v) => v.tag_type(),
             NBTTag::LongArray(v) => v.tag_type(),
+            NBTTag::Unknown(_) => NBTTagType::Unknown,
         }
     }
 
     /// Creates a [View] for the NBT tag for easy reading.
-    pub fn view(&self) -> View {
+    pub fn view(&self) -> View<'_> {
         View::new(self)
     }
 
+    /// Wraps the NBT tag in a [SharedTag], which can be cloned cheaply and is only copied once it
+    /// is mutated.
+    pub fn shared(self) -> SharedTag {
+        SharedTag::new(self)
+    }
+
+    /// Returns the sole element of the tag if it is a [tag::List] or [tag::Compound] containing
+    /// exactly one element.
+    ///
+    /// Returns [None] for any other tag, including empty containers and containers with more than
+    /// one element.
+    pub fn unwrap_single(&self) -> Option<&NBTTag> {
+        match self {
+            NBTTag::List(v) if v.len() == 1 => v.first(),
+            NBTTag::Compound(v) if v.len() == 1 => v.values().next(),
+            _ => None,
+        }
+    }
+
+    /// Gets a nested NBT tag mutably by following a textual path such as `[0].tag.Name`, starting
+    /// at this tag, which may be of any type. An empty path refers to the tag itself.
+    ///
+    /// Unlike [tag::Compound::get_str_path], this also works when the root is a list tag, in which
+    /// case the path starts with an index. See [Path] for the path syntax. Returns `None` if the
+    /// path could not be parsed or if no tag exists at the path.
+    pub fn get_path_mut(&mut self, path: &str) -> Option<&mut NBTTag> {
+        if path.is_empty() {
+            return Some(self);
+        }
+        let path: Path = path.parse().ok()?;
+        self.get_parts_mut(&path.0)
+    }
+
+    /// Gets a nested NBT tag mutably by following path parts, starting at this tag. No parts
+    /// refer to the tag itself.
+    pub(crate) fn get_parts_mut<'a>(
+        &mut self,
+        parts: impl IntoIterator<Item = &'a PathPart>,
+    ) -> Option<&mut NBTTag> {
+        let mut tag = self;
+        for part in parts {
+            tag = match (tag, part) {
+                (NBTTag::Compound(v), part) => v.get_part_mut(part)?,
+                (NBTTag::List(v), PathPart::Element(i)) => v.get_mut(*i)?,
+                _ => return None,
+            };
+        }
+        Some(tag)
+    }
+
+    /// Returns the deepest nesting of compound and list tags in the tag, where a compound or list
+    /// tag at the root has a depth of 1 and any other tag has a depth of 0.
+    ///
+    /// This uses the same definition of depth as [ReadOpts::max_depth], so the tag can be read
+    /// back with a limit of at least this depth. The tree is traversed iteratively, so even
+    /// pathologically deep trees cannot overflow the stack.
+    pub fn max_depth(&self) -> usize {
+        let mut max = 0;
+        let mut stack = vec![(self, 1)];
+        while let Some((tag, depth)) = stack.pop() {
+            match tag {
+                NBTTag::Compound(v) => {
+                    max = max.max(depth);
+                    stack.extend(v.values().map(|v| (v, depth + 1)));
+                }
+                NBTTag::List(v) => {
+                    max = max.max(depth);
+                    stack.extend(v.iter().map(|v| (v, depth + 1)));
+                }
+                _ => {}
+            }
+        }
+        max
+    }
+
+    /// Returns the structure of the tag and all of its children without any of their values, which
+    /// can be used to describe the format of sample data.
+    ///
+    /// As all elements of a list have the same type, only the structure of the first element is
+    /// recorded. Note that for lists of compounds, the other elements may have different keys.
+    pub fn type_tree(&self) -> TypeNode {
+        match self {
+            NBTTag::Compound(v) => TypeNode::Compound(
+                v.iter()
+                    .map(|(key, value)| (key.clone(), value.type_tree()))
+                    .collect(),
+            ),
+            NBTTag::List(v) => TypeNode::List {
+                element: v.first().map(|v| Box::new(v.type_tree())),
+                len: v.len(),
+            },
+            _ => TypeNode::Scalar(self.tag_type()),
+        }
+    }
+
+    /// Returns a short, one-line description of the tag, such as `Int(5)` or `Compound{3 keys}`,
+    /// for use in tree views and logs.
+    ///
+    /// Unlike [Debug], this does not include the children of container tags, only how many there
+    /// are. Long strings are shortened.
+    pub fn summary(&self) -> String {
+        const MAX_STRING_CHARS: usize = 64;
+
+        fn count(n: usize, unit: &str) -> String {
+            if n == 1 {
+                format!("{{1 {unit}}}")
+            } else {
+                format!("{{{n} {unit}s}}")
+            }
+        }
+
+        match self {
+            NBTTag::Byte(v) => format!("Byte({})", v.0),
+            NBTTag::Short(v) => format!("Short({})", v.0),
+            NBTTag::Int(v) => format!("Int({})", v.0),
+            NBTTag::Long(v) => format!("Long({})", v.0),
+            NBTTag::Float(v) => format!("Float({:?})", v.0),
+            NBTTag::Double(v) => format!("Double({:?})", v.0),
+            NBTTag::String(v) => {
+                let s = v.to_string_lossy();
+                match s.char_indices().nth(MAX_STRING_CHARS) {
+                    Some((i, _)) => format!("String({:?}...)", &s[..i]),
+                    None => format!("String({s:?})"),
+                }
+            }
+            NBTTag::Compound(v) => format!("Compound{}", count(v.len(), "key")),
+            NBTTag::List(v) => match v.first() {
+                Some(first) => format!("List<{}>{}", first.tag_type(), count(v.len(), "element")),
+                None => format!("List{}", count(0, "element")),
+            },
+            NBTTag::ByteArray(v) => format!("ByteArray{}", count(v.len(), "element")),
+            NBTTag::IntArray(v) => format!("IntArray{}", count(v.len(), "element")),
+            NBTTag::LongArray(v) => format!("LongArray{}", count(v.len(), "element")),
+            NBTTag::Unknown(v) => format!(
+                "Unknown({}){}",
+                v.id,
+                count(v.len.max(0) as usize, "element")
+            ),
+        }
+    }
+
+    /// Converts between lists of byte, int or long tags and the corresponding array tags in the tag
+    /// and all of its children, in the specified direction.
+    ///
+    /// This smooths over data sources that store arrays as lists or the other way around. Empty
+    /// lists are never converted into arrays, as the type of their elements is unknown.
+    pub fn normalize_arrays(&mut self, direction: ArrayDirection) {
+        match self {
+            NBTTag::Compound(v) => v.values_mut().for_each(|v| v.normalize_arrays(direction)),
+            NBTTag::List(v) => {
+                v.iter_mut().for_each(|v| v.normalize_arrays(direction));
+                if direction != ArrayDirection::ToArrays || v.is_empty() {
+                    return;
+                }
+                let array = match v[0] {
+                    NBTTag::Byte(_) => v
+                        .iter()
+                        .map(|v| match v {
+                            NBTTag::Byte(b) => Some(b.0),
+                            _ => None,
+                        })
+                        .collect::<Option<_>>()
+                        .map(|v| NBTTag::ByteArray(tag::ByteArray(v))),
+                    NBTTag::Int(_) => v
+                        .iter()
+                        .map(|v| match v {
+                            NBTTag::Int(i) => Some(i.0),
+                            _ => None,
+                        })
+                        .collect::<Option<_>>()
+                        .map(|v| NBTTag::IntArray(tag::IntArray(v))),
+                    NBTTag::Long(_) => v
+                        .iter()
+                        .map(|v| match v {
+                            NBTTag::Long(l) => Some(l.0),
+                            _ => None,
+                        })
+                        .collect::<Option<_>>()
+                        .map(|v| NBTTag::LongArray(tag::LongArray(v))),
+                    _ => None,
+                };
+                if let Some(array) = array {
+                    *self = array;
+                }
+            }
+            NBTTag::ByteArray(v) if direction == ArrayDirection::ToLists => {
+                *self = NBTTag::List(
+                    v.iter()
+                        .map(|b| NBTTag::Byte(tag::Byte(*b)))
+                        .collect::<Vec<_>>()
+                        .into(),
+                );
+            }
+            NBTTag::IntArray(v) if direction == ArrayDirection::ToLists => {
+                *self = NBTTag::List(
+                    v.iter()
+                        .map(|i| NBTTag::Int(tag::Int(*i)))
+                        .collect::<Vec<_>>()
+                        .into(),
+                );
+            }
+            NBTTag::LongArray(v) if direction == ArrayDirection::ToLists => {
+                *self = NBTTag::List(
+                    v.iter()
+                        .map(|l| NBTTag::Long(tag::Long(*l)))
+                        .collect::<Vec<_>>()
+                        .into(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Transforms the tag and all of its children into a canonical form, so that tags that only
+    /// differ in ways that do not matter to their meaning are written as identical bytes.
+    ///
+    /// The entries of every compound are sorted by their keys, and all NaN float and double
+    /// values are replaced with [f32::NAN] and [f64::NAN] respectively, just like
+    /// [ReadOpts::canonicalize_nan] does while reading. Lists and arrays are additionally
+    /// normalized if [CanonOpts::normalize_arrays] is set.
+    pub fn canonicalize(&mut self, opts: &CanonOpts) {
+        if let Some(direction) = opts.normalize_arrays {
+            self.normalize_arrays(direction);
+        }
+        self.canonicalize_inner();
+    }
+
+    fn canonicalize_inner(&mut self) {
+        match self {
+            NBTTag::Compound(v) => {
+                v.0.sort_unstable_keys();
+                v.values_mut().for_each(NBTTag::canonicalize_inner);
+            }
+            NBTTag::List(v) => v.iter_mut().for_each(NBTTag::canonicalize_inner),
+            NBTTag::Float(v) if v.is_nan() => v.0 = f32::NAN,
+            NBTTag::Double(v) if v.is_nan() => v.0 = f64::NAN,
+            _ => {}
+        }
+    }
+
+    /// Truncates every [tag::String] in the tag and its children whose encoded length exceeds
+    /// `max_bytes` bytes, so that data which would otherwise fail to write can be salvaged.
+    ///
+    /// Strings are measured by the length of their modified UTF-8 encoding, which is how they are
+    /// written, and are always cut at a character boundary. Strings that are not valid UTF-8 are
+    /// cut at the byte limit. Compound keys are left untouched.
+    ///
+    /// Note that this silently discards data. Only use this when losing the end of over-long
+    /// strings is preferable to failing the write altogether.
+    pub fn truncate_strings(&mut self, max_bytes: usize) {
+        match self {
+            NBTTag::String(tag::String::Utf8(s)) => {
+                let mut len = 0;
+                for (i, c) in s.char_indices() {
+                    len += encode::modified_utf8_len(c);
+                    if len > max_bytes {
+                        s.truncate(i);
+                        break;
+                    }
+                }
+            }
+            NBTTag::String(tag::String::Bytes(b)) => b.truncate(max_bytes),
+            NBTTag::Compound(v) => v.values_mut().for_each(|v| v.truncate_strings(max_bytes)),
+            NBTTag::List(v) => v.iter_mut().for_each(|v| v.truncate_strings(max_bytes)),
+            _ => {}
+        }
+    }
+
     /// Attempts to read the data from a buffer into an NBT value using the specified [Reader]
     /// encoding.
     pub fn read(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
+        Self::read_with(buf, r, &ReadOpts::default())
+    }
+
+    /// Attempts to read the data from a buffer into an NBT value using the specified [Reader]
+    /// encoding and [ReadOpts].
+    ///
+    /// If the root tag is an end tag, which some tools write to represent an empty document, only
+    /// that single byte is consumed and a [ReadError::EmptyDocument] error is returned.
+    pub fn read_with(buf: &mut impl Read, r: &impl Reader, opts: &ReadOpts) -> decode::Res<Self> {
+        let mut buf = LimitedRead::new(buf, opts.max_bytes);
+        let res = Self::read_root(&mut buf, r, &mut ReadCtx::new(opts));
+        buf.finish(res, opts.max_bytes)
+    }
+
+    /// Attempts to read the data from a buffer into an NBT value using the specified [Reader]
+    /// encoding, returning the name of the root tag along with the value.
+    ///
+    /// The name can be written back using [Self::write_with_name], so that it is preserved when a
+    /// file is rewritten. An empty document results in a [ReadError::EmptyDocument] error, just
+    /// like with [Self::read].
+    pub fn read_with_name(buf: &mut impl Read, r: &impl Reader) -> decode::Res<(String, Self)> {
+        let (tag_id, name) = Self::read_header(buf, r)?;
+        if tag_id == ids::END {
+            return Err(ErrorPath::new(ReadError::EmptyDocument));
+        }
+        let tag = Self::read_payload_with(tag_id, buf, r, &ReadOpts::default())?;
+        Ok((name, tag))
+    }
+
+    /// Attempts to read an optional NBT value from a buffer using the specified [Reader] encoding.
+    ///
+    /// Some fields in the Minecraft: Java Edition network protocol write a single end tag to
+    /// indicate that no NBT data is present. Such a field is read as `None`, consuming only that
+    /// byte, rather than failing with a [ReadError::EmptyDocument] error like [Self::read] does.
+    pub fn read_optional(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Option<Self>> {
+        match Self::read(buf, r) {
+            Err(err) if matches!(err.inner, ReadError::EmptyDocument) => Ok(None),
+            res => res.map(Some),
+        }
+    }
+
+    /// Attempts to read the data from a buffer into an NBT value using the specified [Reader]
+    /// encoding and [ReadOpts], collecting any recoverable problems in the data as [Warning]s
+    /// rather than silently accepting them.
+    ///
+    /// Problems that cannot be recovered from still fail reading, just like with
+    /// [Self::read_with]. See [WarningReason] for the problems that are reported.
+    pub fn read_with_warnings(
+        buf: &mut impl Read,
+        r: &impl Reader,
+        opts: &ReadOpts,
+    ) -> decode::Res<(Self, Vec<Warning>)> {
+        let mut buf = LimitedRead::new(buf, opts.max_bytes);
+        let mut ctx = ReadCtx::with_warnings(opts);
+        let res = Self::read_root(&mut buf, r, &mut ctx);
+        let tag = buf.finish(res, opts.max_bytes)?;
+        Ok((tag, ctx.into_warnings()))
+    }
+
+    /// Reads the header and payload of the root tag.
+    fn read_root(buf: &mut impl Read, r: &impl Reader, ctx: &mut ReadCtx) -> decode::Res<Self> {
+        let tag_id = r.u8(buf)?;
+        if tag_id == ids::END {
+            // An end tag has no name or payload, so nothing else belongs to the document.
+            return Err(ErrorPath::new(ReadError::EmptyDocument));
+        }
+        r.root_name(buf)?;
+        Self::read_payload(tag_id, buf, r, ctx)
+    }
+
+    /// Reads only the header of the root tag using the specified [Reader] encoding, returning the
+    /// id of the root tag and its name. The buffer is left positioned at the start of the payload,
+    /// which can then be read using [Self::read_payload_with].
+    ///
+    /// If the root tag is an end tag, it has no name or payload, so only that single byte is
+    /// consumed and an empty name is returned.
+    pub fn read_header(buf: &mut impl Read, r: &impl Reader) -> decode::Res<(u8, String)> {
+        let tag_id = r.u8(buf)?;
+        if tag_id == ids::END {
+            return Ok((tag_id, String::new()));
+        }
+        Ok((tag_id, r.root_name(buf)?))
+    }
+
+    /// Attempts to read the payload of a tag with the provided tag id from a buffer using the
+    /// specified [Reader] encoding and [ReadOpts], such as the payload following a header that
+    /// was read using [Self::read_header].
+    pub fn read_payload_with(
+        tag_id: u8,
+        buf: &mut impl Read,
+        r: &impl Reader,
+        opts: &ReadOpts,
+    ) -> decode::Res<Self> {
+        let mut buf = LimitedRead::new(buf, opts.max_bytes);
+        let res = Self::read_payload(tag_id, &mut buf, r, &mut ReadCtx::new(opts));
+        buf.finish(res, opts.max_bytes)
+    }
+
+    /// Attempts to read all NBT values stored back-to-back in the buffer using the specified
+    /// [Reader] encoding, until the end of the buffer is reached.
+    ///
+    /// This is the layout used by Minecraft: Bedrock Edition to store multiple entities or block
+    /// entities of a chunk under a single LevelDB key, using the
+    /// [crate::encoding::LittleEndian] encoding.
+    pub fn read_all(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Vec<Self>> {
+        let mut tags = Vec::new();
+        loop {
+            let mut first = [0];
+            match buf.read(&mut first) {
+                Ok(0) => return Ok(tags),
+                Ok(_) => {}
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(ErrorPath::new(err.into())),
+            }
+            let tag = Self::read(&mut first.as_slice().chain(&mut *buf), r)
+                .map_err(|err| err.prepend(PathPart::Element(tags.len())))?;
+            tags.push(tag);
+        }
+    }
+
+    /// Attempts to read an NBT value that occupies exactly `limit` bytes of the buffer using the
+    /// specified [Reader] encoding.
+    ///
+    /// Reads past the limit fail as if the end of the buffer was reached, so malformed data can
+    /// never consume bytes beyond its frame. Returns an error if the value does not consume the
+    /// entire limit.
+    pub fn read_limited(buf: &mut impl Read, r: &impl Reader, limit: usize) -> decode::Res<Self> {
+        let mut buf = buf.take(limit as u64);
+        let tag = Self::read(&mut buf, r)?;
+        match buf.limit() {
+            0 => Ok(tag),
+            remaining => Err(ErrorPath::new(ReadError::TrailingBytes(remaining as usize))),
+        }
+    }
+
+    /// Attempts to read an NBT value from the start of a byte slice using the specified [Reader]
+    /// encoding. Any bytes after the value are ignored.
+    ///
+    /// With the [encoding::BigEndian] and [encoding::LittleEndian] encodings, the elements of
+    /// arrays are converted in chunks of a few kilobytes rather than one at a time, and are only
+    /// copied from the slice into the resulting tag. This makes it suitable for reading large files
+    /// that have been memory mapped, see [Self::read_from_mmap].
+    pub fn read_from_slice(data: &[u8], r: &impl Reader) -> decode::Res<Self> {
+        Self::read(&mut &data[..], r)
+    }
+
+    /// Attempts to read an NBT value from the start of a memory mapped file using the specified
+    /// [Reader] encoding. This is an alias for [Self::read_from_slice].
+    ///
+    /// Only the pages of the file that are actually read are loaded into memory, and no copy of
+    /// the file is made. Note that the resulting tag owns all of its data, so the contents of any
+    /// arrays are still copied out of the mapped region once.
+    pub fn read_from_mmap(data: &[u8], r: &impl Reader) -> decode::Res<Self> {
+        Self::read_from_slice(data, r)
+    }
+
+    /// Reads only the top-level entries of a root [tag::Compound], returning the key and
+    /// [NBTTagType] of each entry in the order they were read.
+    ///
+    /// The payloads of the entries are skipped without constructing any nested values, which makes
+    /// this considerably faster than [Self::read] when only the keys are of interest.
+    ///
+    /// Returns an error if the root tag is not a compound tag.
+    pub fn read_shallow(
+        buf: &mut impl Read,
+        r: &impl Reader,
+    ) -> decode::Res<Vec<(String, NBTTagType)>> {
+        Self::read_shallow_with(buf, r, &ReadOpts::default())
+    }
+
+    /// Reads only the top-level entries of a root [tag::Compound] using the specified [Reader]
+    /// encoding and [ReadOpts], like [Self::read_shallow].
+    ///
+    /// Since no values are constructed, only [ReadOpts::unknown_arrays] and [ReadOpts::max_bytes]
+    /// apply. Entries that are unknown tags are returned with the type [NBTTagType::Unknown].
+    pub fn read_shallow_with(
+        buf: &mut impl Read,
+        r: &impl Reader,
+        opts: &ReadOpts,
+    ) -> decode::Res<Vec<(String, NBTTagType)>> {
+        let mut buf = LimitedRead::new(buf, opts.max_bytes);
+        let res = Self::read_shallow_entries(&mut buf, r, opts);
+        buf.finish(res, opts.max_bytes)
+    }
+
+    fn read_shallow_entries(
+        buf: &mut impl Read,
+        r: &impl Reader,
+        opts: &ReadOpts,
+    ) -> decode::Res<Vec<(String, NBTTagType)>> {
         let tag_id = r.u8(buf)?;
-        r.string(buf)?;
-        Self::read_payload(tag_id, buf, r)
+        if tag_id != ids::COMPOUND {
+            return Err(ErrorPath::new(ReadError::UnexpectedTag(
+                ids::COMPOUND,
+                tag_id,
+            )));
+        }
+        r.root_name(buf)?;
+
+        let mut entries = Vec::new();
+        loop {
+            let content_type = r.u8(buf)?;
+            if content_type == ids::END {
+                break;
+            }
+            let name = r.string(buf)?;
+            let tag_type = match NBTTagType::from_id(content_type) {
+                Some(tag_type) => tag_type,
+                None if opts.unknown_arrays.contains_key(&content_type) => NBTTagType::Unknown,
+                None => {
+                    return Err(ErrorPath::new_with_path(
+                        ReadError::UnknownTagType(content_type),
+                        Path::from_single(PathPart::MapKey(name)),
+                    ))
+                }
+            };
+            Self::skip_payload(content_type, buf, r, &opts.unknown_arrays)
+                .map_err(|err| err.prepend(PathPart::MapKey(name.clone())))?;
+            entries.push((name, tag_type));
+        }
+        Ok(entries)
     }
 
-    fn read_payload(tag_id: u8, buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
+    /// Reads the payload of a tag with the provided tag id.
+    ///
+    /// Nested tags are read iteratively rather than recursively, so that reading deeply nested
+    /// data cannot overflow the stack.
+    fn read_payload(
+        tag_id: u8,
+        buf: &mut impl Read,
+        r: &impl Reader,
+        ctx: &mut ReadCtx,
+    ) -> decode::Res<Self> {
+        let mut stack = Vec::new();
+        Self::read_payload_inner(tag_id, buf, r, ctx, &mut stack).map_err(|mut err| {
+            for frame in stack.into_iter().rev() {
+                match frame {
+                    ReadFrame::Compound { key: Some(key), .. } => {
+                        err = err.prepend(PathPart::MapKey(key))
+                    }
+                    ReadFrame::List { vec, .. } => err = err.prepend(PathPart::Element(vec.len())),
+                    _ => {}
+                }
+            }
+            err
+        })
+    }
+
+    fn read_payload_inner(
+        tag_id: u8,
+        buf: &mut impl Read,
+        r: &impl Reader,
+        ctx: &mut ReadCtx,
+        stack: &mut Vec<ReadFrame>,
+    ) -> decode::Res<Self> {
+        let mut next_id = tag_id;
+        loop {
+            let mut value = match next_id {
+                ids::COMPOUND | ids::LIST => match Self::enter_nested(next_id, buf, r, ctx)? {
+                    Ok(frame) => {
+                        stack.push(frame);
+                        None
+                    }
+                    Err(tag) => Some(tag),
+                },
+                _ => Some(Self::read_flat_payload(next_id, buf, r, ctx)?),
+            };
+
+            // Add finished values to their parent, until a parent has another child to read.
+            loop {
+                let Some(frame) = stack.last_mut() else {
+                    return Ok(value.expect("a value is finished when the stack is empty"));
+                };
+                match frame {
+                    ReadFrame::List {
+                        vec,
+                        content_type,
+                        len,
+                    } => {
+                        if let Some(value) = value.take() {
+                            ctx.pop_path();
+                            vec.push(value);
+                        }
+                        if vec.len() < *len {
+                            let i = vec.len();
+                            ctx.push_path(|| PathPart::Element(i));
+                            next_id = *content_type;
+                            break;
+                        }
+                        value = Some(NBTTag::List(std::mem::take(vec).into()));
+                    }
+                    ReadFrame::Compound { map, key } => {
+                        if let Some(value) = value.take() {
+                            let name = key.take().expect("a key is set while reading a value");
+                            if ctx.collects_warnings() && map.contains_key(&name) {
+                                ctx.warn(WarningReason::DuplicateKey);
+                            }
+                            ctx.pop_path();
+                            map.insert(name, value);
+                        }
+                        let content_type = r.u8(buf)?;
+                        if content_type != ids::END {
+                            ctx.take_elements(1)?;
+                            let name = read_string(buf, r, ctx)?;
+                            ctx.push_path(|| PathPart::MapKey(name.clone()));
+                            *key = Some(name);
+                            next_id = content_type;
+                            break;
+                        }
+                        value = Some(NBTTag::Compound(std::mem::take(map).into()));
+                    }
+                }
+                stack.pop();
+                ctx.exit();
+            }
+        }
+    }
+
+    /// Enters a compound or list tag by reading its header, if any. Returns the frame in which
+    /// its children are read, or the finished tag if it has no children to read or exceeds the
+    /// maximum depth and was truncated.
+    fn enter_nested(
+        tag_id: u8,
+        buf: &mut impl Read,
+        r: &impl Reader,
+        ctx: &mut ReadCtx,
+    ) -> decode::Res<Result<ReadFrame, Self>> {
+        if !ctx.enter()? {
+            NBTTag::skip_payload(tag_id, buf, r, &ctx.opts().unknown_arrays)?;
+            ctx.exit();
+            return Ok(Err(match tag_id {
+                ids::LIST => NBTTag::List(tag::List::default()),
+                _ => NBTTag::Compound(tag::Compound::default()),
+            }));
+        }
+        if tag_id == ids::COMPOUND {
+            return Ok(Ok(ReadFrame::Compound {
+                map: IndexMap::new(),
+                key: None,
+            }));
+        }
+
+        let content_type = r.u8(buf)?;
+        let len = r.i32(buf)?;
+        let len = usize::try_from(len).map_err(|_| {
+            ErrorPath::new(ReadError::SeqLengthViolation(
+                i32::MAX as usize,
+                len as usize,
+            ))
+        })?;
+        ctx.take_elements(len)?;
+        if len == 0 {
+            if content_type > ids::LONG_ARRAY
+                && !ctx.opts().unknown_arrays.contains_key(&content_type)
+            {
+                ctx.warn(WarningReason::UnknownEmptyListType(content_type));
+            }
+            ctx.exit();
+            return Ok(Err(NBTTag::List(tag::List(Vec::new(), Some(content_type)))));
+        }
+        Ok(Ok(ReadFrame::List {
+            vec: Vec::with_capacity(decode::prealloc_capacity::<NBTTag>(len)),
+            content_type,
+            len,
+        }))
+    }
+
+    /// Reads the payload of a tag with the provided tag id that is not a compound or list tag.
+    fn read_flat_payload(
+        tag_id: u8,
+        buf: &mut impl Read,
+        r: &impl Reader,
+        ctx: &mut ReadCtx,
+    ) -> decode::Res<Self> {
         match tag_id {
-            1 => Ok(NBTTag::Byte(tag::Byte::read_payload(buf, r)?)),
-            2 => Ok(NBTTag::Short(tag::Short::read_payload(buf, r)?)),
-            3 => Ok(NBTTag::Int(tag::Int::read_payload(buf, r)?)),
-            4 => Ok(NBTTag::Long(tag::Long::read_payload(buf, r)?)),
-            5 => Ok(NBTTag::Float(tag::Float::read_payload(buf, r)?)),
-            6 => Ok(NBTTag::Double(tag::Double::read_payload(buf, r)?)),
-            8 => Ok(NBTTag::String(tag::String::read_payload(buf, r)?)),
-            10 => Ok(NBTTag::Compound(tag::Compound::read_payload(buf, r)?)),
-            9 => Ok(NBTTag::List(tag::List::read_payload(buf, r)?)),
-            7 => Ok(NBTTag::ByteArray(tag::ByteArray::read_payload(buf, r)?)),
-            11 => Ok(NBTTag::IntArray(tag::IntArray::read_payload(buf, r)?)),
-            12 => Ok(NBTTag::LongArray(tag::LongArray::read_payload(buf, r)?)),
-            other => Err(ErrorPath::new(ReadError::UnknownTagType(other))),
+            ids::BYTE => Ok(NBTTag::Byte(tag::Byte::read_payload(buf, r, ctx)?)),
+            ids::SHORT => Ok(NBTTag::Short(tag::Short::read_payload(buf, r, ctx)?)),
+            ids::INT => Ok(NBTTag::Int(tag::Int::read_payload(buf, r, ctx)?)),
+            ids::LONG => Ok(NBTTag::Long(tag::Long::read_payload(buf, r, ctx)?)),
+            ids::FLOAT => Ok(NBTTag::Float(tag::Float::read_payload(buf, r, ctx)?)),
+            ids::DOUBLE => Ok(NBTTag::Double(tag::Double::read_payload(buf, r, ctx)?)),
+            ids::STRING => Ok(NBTTag::String(tag::String::read_payload(buf, r, ctx)?)),
+            ids::BYTE_ARRAY => Ok(NBTTag::ByteArray(tag::ByteArray::read_payload(
+                buf, r, ctx,
+            )?)),
+            ids::INT_ARRAY => Ok(NBTTag::IntArray(tag::IntArray::read_payload(buf, r, ctx)?)),
+            ids::LONG_ARRAY => Ok(NBTTag::LongArray(tag::LongArray::read_payload(
+                buf, r, ctx,
+            )?)),
+            other => match ctx.opts().unknown_arrays.get(&other) {
+                Some(size) => Ok(NBTTag::Unknown(tag::Unknown::read_payload(
+                    other, *size, buf, r, ctx,
+                )?)),
+                None => Err(ErrorPath::new(ReadError::UnknownTagType(other))),
+            },
+        }
+    }
+
+    /// Reads the payload of a tag with the provided tag id, discarding the data without
+    /// constructing any NBT values.
+    ///
+    /// Nested tags are skipped iteratively rather than recursively, so that skipping deeply nested
+    /// data cannot overflow the stack. Unknown tags are skipped if their id is present in
+    /// `unknown_arrays`, in the same way as [ReadOpts::unknown_arrays].
+    fn skip_payload(
+        tag_id: u8,
+        buf: &mut impl Read,
+        r: &impl Reader,
+        unknown_arrays: &HashMap<u8, usize>,
+    ) -> decode::Res<()> {
+        let mut stack = Vec::new();
+        Self::skip_payload_inner(tag_id, buf, r, unknown_arrays, &mut stack).map_err(|mut err| {
+            for frame in stack.into_iter().rev() {
+                match frame {
+                    SkipFrame::Compound(Some(key)) => err = err.prepend(PathPart::MapKey(key)),
+                    SkipFrame::List { next, .. } if next > 0 => {
+                        err = err.prepend(PathPart::Element(next - 1))
+                    }
+                    _ => {}
+                }
+            }
+            err
+        })
+    }
+
+    fn skip_payload_inner(
+        tag_id: u8,
+        buf: &mut impl Read,
+        r: &impl Reader,
+        unknown_arrays: &HashMap<u8, usize>,
+        stack: &mut Vec<SkipFrame>,
+    ) -> decode::Res<()> {
+        let mut next_id = Some(tag_id);
+        loop {
+            if let Some(tag_id) = next_id.take() {
+                match tag_id {
+                    ids::BYTE => r.i8(buf).map(|_| ())?,
+                    ids::SHORT => r.i16(buf).map(|_| ())?,
+                    ids::INT => r.i32(buf).map(|_| ())?,
+                    ids::LONG => r.i64(buf).map(|_| ())?,
+                    ids::FLOAT => r.f32(buf).map(|_| ())?,
+                    ids::DOUBLE => r.f64(buf).map(|_| ())?,
+                    ids::STRING => match r.string(buf) {
+                        Err(ErrorPath {
+                            inner: ReadError::InvalidString(_),
+                            path: _,
+                        }) => {}
+                        other => other.map(|_| ())?,
+                    },
+                    ids::COMPOUND => stack.push(SkipFrame::Compound(None)),
+                    ids::LIST => {
+                        let content_type = r.u8(buf)?;
+                        let len = r.i32(buf)?;
+                        if len < 0 {
+                            return Err(ErrorPath::new(ReadError::SeqLengthViolation(
+                                i32::MAX as usize,
+                                len as usize,
+                            )));
+                        }
+                        stack.push(SkipFrame::List {
+                            content_type,
+                            len: len as usize,
+                            next: 0,
+                        });
+                    }
+                    ids::BYTE_ARRAY | ids::INT_ARRAY | ids::LONG_ARRAY => {
+                        let len = r.i32(buf)?;
+                        if len < 0 {
+                            return Err(ErrorPath::new(ReadError::SeqLengthViolation(
+                                i32::MAX as usize,
+                                len as usize,
+                            )));
+                        }
+                        for i in 0..len {
+                            match tag_id {
+                                ids::BYTE_ARRAY => r.i8(buf).map(|_| ()),
+                                ids::INT_ARRAY => r.i32(buf).map(|_| ()),
+                                _ => r.i64(buf).map(|_| ()),
+                            }
+                            .map_err(|err| err.prepend(PathPart::Element(i as usize)))?;
+                        }
+                    }
+                    other => {
+                        let Some(size) = unknown_arrays.get(&other) else {
+                            return Err(ErrorPath::new(ReadError::UnknownTagType(other)));
+                        };
+                        let len = r.i32(buf)?;
+                        let byte_len = usize::try_from(len)
+                            .ok()
+                            .and_then(|len| len.checked_mul(*size))
+                            .ok_or_else(|| {
+                                ErrorPath::new(ReadError::SeqLengthViolation(
+                                    i32::MAX as usize,
+                                    len as usize,
+                                ))
+                            })?;
+                        let skipped = io::copy(&mut buf.take(byte_len as u64), &mut io::sink())
+                            .map_err(|x| ErrorPath::new(x.into()))?;
+                        if skipped < byte_len as u64 {
+                            return Err(ErrorPath::new(
+                                io::Error::from(io::ErrorKind::UnexpectedEof).into(),
+                            ));
+                        }
+                    }
+                }
+            }
+
+            match stack.last_mut() {
+                None => return Ok(()),
+                Some(SkipFrame::Compound(key)) => {
+                    *key = None;
+                    let content_type = r.u8(buf)?;
+                    if content_type == ids::END {
+                        stack.pop();
+                    } else {
+                        *key = Some(r.string(buf)?);
+                        next_id = Some(content_type);
+                    }
+                }
+                Some(SkipFrame::List {
+                    content_type,
+                    len,
+                    next,
+                }) => {
+                    if *next == *len {
+                        stack.pop();
+                    } else {
+                        *next += 1;
+                        next_id = Some(*content_type);
+                    }
+                }
+            }
         }
     }
 
     /// Attempts to write the NBT data into a buffer using the specified [Writer] encoding.
     pub fn write(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
-        w.write_u8(buf, self.tag_id())?;
-        w.write_string(buf, "")?;
-        self.write_payload(buf, w)
+        self.write_with(buf, w, &WriteOpts::default())
     }
 
-    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
+    /// Attempts to write the NBT data into a buffer using the specified [Writer] encoding, using
+    /// the provided name as the name of the root tag rather than an empty name.
+    ///
+    /// This is a shorthand for [Self::write_with] with [WriteOpts::root_name] set.
+    pub fn write_with_name(
+        &self,
+        name: &str,
+        buf: &mut impl Write,
+        w: &impl Writer,
+    ) -> encode::Res {
+        let opts = WriteOpts {
+            root_name: Some(name.to_string()),
+            ..Default::default()
+        };
+        self.write_with(buf, w, &opts)
+    }
+
+    /// Attempts to write an optional NBT value into a buffer using the specified [Writer]
+    /// encoding.
+    ///
+    /// If no tag is provided, a single end tag is written. This is how the Minecraft: Java
+    /// Edition network protocol indicates that no NBT data is present, and reads back as `None`
+    /// using [Self::read_optional].
+    pub fn write_optional(
+        tag: Option<&NBTTag>,
+        buf: &mut impl Write,
+        w: &impl Writer,
+    ) -> encode::Res {
+        match tag {
+            Some(tag) => tag.write(buf, w),
+            None => w.write_u8(buf, ids::END),
+        }
+    }
+
+    /// Attempts to write the NBT data into a buffer using the specified [Writer] encoding and
+    /// [WriteOpts].
+    ///
+    /// The path of the returned error always points to the tag that was being written when the
+    /// error occurred, including for IO errors, so that it is known how far the write progressed.
+    pub fn write_with(
+        &self,
+        buf: &mut impl Write,
+        w: &impl Writer,
+        opts: &WriteOpts,
+    ) -> encode::Res {
+        opts.write_header(buf, w, self.tag_id())?;
+        self.write_payload(buf, w, opts)
+    }
+
+    fn write_payload(
+        &self,
+        buf: &mut impl Write,
+        w: &impl Writer,
+        opts: &WriteOpts,
+    ) -> encode::Res {
         match self {
-            NBTTag::Byte(tag) => tag.write_payload(buf, w),
-            NBTTag::Short(tag) => tag.write_payload(buf, w),
-            NBTTag::Int(tag) => tag.write_payload(buf, w),
-            NBTTag::Long(tag) => tag.write_payload(buf, w),
-            NBTTag::Float(tag) => tag.write_payload(buf, w),
-            NBTTag::Double(tag) => tag.write_payload(buf, w),
-            NBTTag::String(tag) => tag.write_payload(buf, w),
-            NBTTag::Compound(tag) => tag.write_payload(buf, w),
-            NBTTag::List(tag) => tag.write_payload(buf, w),
-            NBTTag::ByteArray(tag) => tag.write_payload(buf, w),
-            NBTTag::IntArray(tag) => tag.write_payload(buf, w),
-            NBTTag::LongArray(tag) => tag.write_payload(buf, w),
+            NBTTag::Byte(tag) => tag.write_payload(buf, w, opts),
+            NBTTag::Short(tag) => tag.write_payload(buf, w, opts),
+            NBTTag::Int(tag) => tag.write_payload(buf, w, opts),
+            NBTTag::Long(tag) => tag.write_payload(buf, w, opts),
+            NBTTag::Float(tag) => tag.write_payload(buf, w, opts),
+            NBTTag::Double(tag) => tag.write_payload(buf, w, opts),
+            NBTTag::String(tag) => tag.write_payload(buf, w, opts),
+            NBTTag::Compound(tag) => tag.write_payload(buf, w, opts),
+            NBTTag::List(tag) => tag.write_payload(buf, w, opts),
+            NBTTag::ByteArray(tag) => tag.write_payload(buf, w, opts),
+            NBTTag::IntArray(tag) => tag.write_payload(buf, w, opts),
+            NBTTag::LongArray(tag) => tag.write_payload(buf, w, opts),
+            NBTTag::Unknown(tag) => tag.write_payload(buf, w, opts),
         }
     }
 
     /// Gets the discriminator of a [NBTTag]'s type used for encoding and decoding.
     pub(crate) fn tag_id(&self) -> u8 {
         match self {
-            NBTTag::Byte(_) => 1,
-            NBTTag::Short(_) => 2,
-            NBTTag::Int(_) => 3,
-            NBTTag::Long(_) => 4,
-            NBTTag::Float(_) => 5,
-            NBTTag::Double(_) => 6,
-            NBTTag::String(_) => 8,
-            NBTTag::Compound(_) => 10,
-            NBTTag::List(_) => 9,
-            NBTTag::ByteArray(_) => 7,
-            NBTTag::IntArray(_) => 11,
-            NBTTag::LongArray(_) => 12,
+            NBTTag::Byte(_) => ids::BYTE,
+            NBTTag::Short(_) => ids::SHORT,
+            NBTTag::Int(_) => ids::INT,
+            NBTTag::Long(_) => ids::LONG,
+            NBTTag::Float(_) => ids::FLOAT,
+            NBTTag::Double(_) => ids::DOUBLE,
+            NBTTag::String(_) => ids::STRING,
+            NBTTag::Compound(_) => ids::COMPOUND,
+            NBTTag::List(_) => ids::LIST,
+            NBTTag::ByteArray(_) => ids::BYTE_ARRAY,
+            NBTTag::IntArray(_) => ids::INT_ARRAY,
+            NBTTag::LongArray(_) => ids::LONG_ARRAY,
+            NBTTag::Unknown(v) => v.id,
+        }
+    }
+}
+
+impl NBTTagType {
+    /// Gets the [NBTTagType] associated with a discriminator used for encoding and decoding.
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            ids::BYTE => Some(NBTTagType::Byte),
+            ids::SHORT => Some(NBTTagType::Short),
+            ids::INT => Some(NBTTagType::Int),
+            ids::LONG => Some(NBTTagType::Long),
+            ids::FLOAT => Some(NBTTagType::Float),
+            ids::DOUBLE => Some(NBTTagType::Double),
+            ids::STRING => Some(NBTTagType::String),
+            ids::COMPOUND => Some(NBTTagType::Compound),
+            ids::LIST => Some(NBTTagType::List),
+            ids::BYTE_ARRAY => Some(NBTTagType::ByteArray),
+            ids::INT_ARRAY => Some(NBTTagType::IntArray),
+            ids::LONG_ARRAY => Some(NBTTagType::LongArray),
+            _ => None,
         }
     }
 }
 
+impl FromStr for NBTTagType {
+    type Err = strum::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "Byte" | "TAG_Byte" => NBTTagType::Byte,
+            "Short" | "TAG_Short" => NBTTagType::Short,
+            "Int" | "TAG_Int" => NBTTagType::Int,
+            "Long" | "TAG_Long" => NBTTagType::Long,
+            "Float" | "TAG_Float" => NBTTagType::Float,
+            "Double" | "TAG_Double" => NBTTagType::Double,
+            "String" | "TAG_String" => NBTTagType::String,
+            "Compound" | "TAG_Compound" => NBTTagType::Compound,
+            "List" | "TAG_List" => NBTTagType::List,
+            "ByteArray" | "TAG_Byte_Array" => NBTTagType::ByteArray,
+            "IntArray" | "TAG_Int_Array" => NBTTagType::IntArray,
+            "LongArray" | "TAG_Long_Array" => NBTTagType::LongArray,
+            _ => return Err(strum::ParseError::VariantNotFound),
+        })
+    }
+}
+
 impl Default for NBTTag {
     fn default() -> Self {
-        Self::Compound(HashMap::new().into())
+        Self::Compound(tag::Compound::default())
     }
 }
 
+/// A container tag that is in the process of being skipped by [NBTTag::skip_payload].
+enum SkipFrame {
+    /// A compound tag, along with the key of the entry that is currently being skipped.
+    Compound(Option<String>),
+    /// A list tag, along with the index of the next element to be skipped.
+    List {
+        content_type: u8,
+        len: usize,
+        next: usize,
+    },
+}
+
+/// A container tag that is in the process of being read by [NBTTag::read_payload].
+enum ReadFrame {
+    /// A compound tag, along with the entries read so far and the key of the entry that is
+    /// currently being read.
+    Compound {
+        map: IndexMap<String, NBTTag>,
+        key: Option<String>,
+    },
+    /// A list tag, along with the elements read so far. The element currently being read is at
+    /// the index of the length of `vec`.
+    List {
+        vec: Vec<NBTTag>,
+        content_type: u8,
+        len: usize,
+    },
+}
+
 /// A trait implemented on all NBT tags to define reading/writing their payload data.
 trait TagIo: Sized {
     /// Attempts to read the payload data from a buffer into an NBT value using the specified
     /// [Reader] encoding.
-    fn read_payload(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self>;
+    fn read_payload(buf: &mut impl Read, r: &impl Reader, ctx: &mut ReadCtx) -> decode::Res<Self>;
     /// Attempts to write the NBT data into a buffer using the specified [Writer] encoding.
-    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res;
+    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer, opts: &WriteOpts)
+        -> encode::Res;
 }
 impl TagIo for tag::Byte {
-    fn read_payload(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
+    fn read_payload(buf: &mut impl Read, r: &impl Reader, _: &mut ReadCtx) -> decode::Res<Self> {
         Ok(r.i8(buf)?.into())
     }
 
-    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
+    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer, _: &WriteOpts) -> encode::Res {
         w.write_i8(buf, self.0)
     }
 }
 impl TagIo for tag::Short {
-    fn read_payload(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
+    fn read_payload(buf: &mut impl Read, r: &impl Reader, _: &mut ReadCtx) -> decode::Res<Self> {
         Ok(r.i16(buf)?.into())
     }
 
-    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
+    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer, _: &WriteOpts) -> encode::Res {
         w.write_i16(buf, self.0)
     }
 }
 impl TagIo for tag::Int {
-    fn read_payload(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
+    fn read_payload(buf: &mut impl Read, r: &impl Reader, _: &mut ReadCtx) -> decode::Res<Self> {
         Ok(r.i32(buf)?.into())
     }
 
-    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
+    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer, _: &WriteOpts) -> encode::Res {
         w.write_i32(buf, self.0)
     }
 }
 impl TagIo for tag::Long {
-    fn read_payload(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
+    fn read_payload(buf: &mut impl Read, r: &impl Reader, _: &mut ReadCtx) -> decode::Res<Self> {
         Ok(r.i64(buf)?.into())
     }
 
-    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
+    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer, _: &WriteOpts) -> encode::Res {
         w.write_i64(buf, self.0)
     }
 }
 impl TagIo for tag::Float {
-    fn read_payload(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
-        Ok(r.f32(buf)?.into())
+    fn read_payload(buf: &mut impl Read, r: &impl Reader, ctx: &mut ReadCtx) -> decode::Res<Self> {
+        let v = r.f32(buf)?;
+        if v.is_nan() && ctx.opts().canonicalize_nan {
+            return Ok(f32::NAN.into());
+        }
+        Ok(v.into())
     }
 
-    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
+    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer, _: &WriteOpts) -> encode::Res {
         w.write_f32(buf, self.0)
     }
 }
 impl TagIo for tag::Double {
-    fn read_payload(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
-        Ok(r.f64(buf)?.into())
+    fn read_payload(buf: &mut impl Read, r: &impl Reader, ctx: &mut ReadCtx) -> decode::Res<Self> {
+        let v = r.f64(buf)?;
+        if v.is_nan() && ctx.opts().canonicalize_nan {
+            return Ok(f64::NAN.into());
+        }
+        Ok(v.into())
     }
 
-    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
+    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer, _: &WriteOpts) -> encode::Res {
         w.write_f64(buf, self.0)
     }
 }
 impl TagIo for tag::String {
-    fn read_payload(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
-        let string = r.string(buf);
+    fn read_payload(buf: &mut impl Read, r: &impl Reader, ctx: &mut ReadCtx) -> decode::Res<Self> {
+        let string = read_string(buf, r, ctx);
         if let Err(ErrorPath {
             inner: ReadError::InvalidString(bytes),
             path: _,
         }) = string
         {
+            ctx.warn(WarningReason::InvalidString);
             Ok(tag::String::Bytes(bytes))
         } else {
             Ok(tag::String::Utf8(string?))
         }
     }
 
-    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
+    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer, _: &WriteOpts) -> encode::Res {
         match self {
             tag::String::Utf8(x) => w.write_string(buf, x.as_str()),
             tag::String::Bytes(x) => {
@@ -273,96 +1255,433 @@ impl TagIo for tag::String {
     }
 }
 impl TagIo for tag::List {
-    fn read_payload(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
-        let content_type = r.u8(buf)?;
-        let len = r.i32(buf)?;
-        if len < 0 {
-            return Err(ErrorPath::new(ReadError::SeqLengthViolation(
-                i32::MAX as usize,
-                len as usize,
-            )));
-        }
-        let mut vec = Vec::with_capacity(len as usize);
-        for i in 0..len {
-            vec.push(
-                NBTTag::read_payload(content_type, buf, r)
-                    .map_err(|err| err.prepend(PathPart::Element(i as usize)))?,
-            );
+    fn read_payload(buf: &mut impl Read, r: &impl Reader, ctx: &mut ReadCtx) -> decode::Res<Self> {
+        match NBTTag::read_payload(ids::LIST, buf, r, ctx)? {
+            NBTTag::List(v) => Ok(v),
+            _ => unreachable!("a list tag is read as a list"),
         }
-        Ok(vec.into())
     }
 
-    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
-        let first_id = if self.0.is_empty() {
-            NBTTag::Byte(0.into()).tag_id()
-        } else {
-            self.0[0].tag_id()
-        };
+    fn write_payload(
+        &self,
+        buf: &mut impl Write,
+        w: &impl Writer,
+        opts: &WriteOpts,
+    ) -> encode::Res {
+        if let Some((i, expected, found)) = self.find_type_mismatch() {
+            return Err(ErrorPath::new_with_path(
+                WriteError::UnexpectedTag(expected, found),
+                Path::from_single(PathPart::Element(i)),
+            ));
+        }
 
-        w.write_u8(buf, first_id)?;
+        w.write_u8(buf, self.content_type_id())?;
         w.write_i32(buf, self.len() as i32)?;
         for (i, v) in self.0.iter().enumerate() {
-            if v.tag_id() != first_id {
-                return Err(ErrorPath::new_with_path(
-                    WriteError::UnexpectedTag(self[0].tag_type(), v.tag_type()),
-                    Path::from_single(PathPart::Element(i)),
-                ));
-            }
-            v.write_payload(buf, w)?;
+            v.write_payload(buf, w, opts)
+                .map_err(|err| err.prepend(PathPart::Element(i)))?;
         }
         Ok(())
     }
 }
 impl TagIo for tag::Compound {
-    fn read_payload(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
-        let mut map = HashMap::new();
-        loop {
-            let content_type = r.u8(buf)?;
-            if content_type == 0 {
-                break;
-            }
-            let name = r.string(buf)?;
-            let value = NBTTag::read_payload(content_type, buf, r)
-                .map_err(|err| err.prepend(PathPart::MapKey(name.clone())))?;
-            map.insert(name, value);
+    fn read_payload(buf: &mut impl Read, r: &impl Reader, ctx: &mut ReadCtx) -> decode::Res<Self> {
+        match NBTTag::read_payload(ids::COMPOUND, buf, r, ctx)? {
+            NBTTag::Compound(v) => Ok(v),
+            _ => unreachable!("a compound tag is read as a compound"),
         }
-        Ok(map.into())
     }
 
-    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
-        for (name, val) in &self.0 {
-            w.write_u8(buf, val.tag_id())?;
-            w.write_string(buf, name)?;
-            val.write_payload(buf, w)?;
+    fn write_payload(
+        &self,
+        buf: &mut impl Write,
+        w: &impl Writer,
+        opts: &WriteOpts,
+    ) -> encode::Res {
+        let mut write_entry = |name: &String, val: &NBTTag| {
+            w.write_u8(buf, val.tag_id())
+                .and_then(|_| {
+                    w.write_string(buf, name).map_err(|err| match err.inner {
+                        WriteError::SeqLengthViolation(_, len) => {
+                            ErrorPath::new(WriteError::KeyTooLong(len))
+                        }
+                        _ => err,
+                    })
+                })
+                .and_then(|_| val.write_payload(buf, w, opts))
+                .map_err(|err| err.prepend(PathPart::MapKey(name.clone())))
+        };
+        match &opts.key_order {
+            None => {
+                for (name, val) in &self.0 {
+                    write_entry(name, val)?;
+                }
+            }
+            Some(order) => {
+                let mut listed = HashSet::new();
+                for key in order {
+                    if let Some((name, val)) = self.0.get_key_value(key.as_str()) {
+                        if listed.insert(name.as_str()) {
+                            write_entry(name, val)?;
+                        }
+                    }
+                }
+                for (name, val) in &self.0 {
+                    if !listed.contains(name.as_str()) {
+                        write_entry(name, val)?;
+                    }
+                }
+            }
         }
         w.write_end(buf)?;
         Ok(())
     }
 }
+/// Reads a string, checking its length prefix against [ReadOpts::max_string_len] before reading
+/// the rest of the string if a maximum length is set.
+fn read_string(buf: &mut impl Read, r: &impl Reader, ctx: &ReadCtx) -> decode::Res<String> {
+    let Some(max) = ctx.opts().max_string_len else {
+        return r.string(buf);
+    };
+    let len = r.string_len(buf)?;
+    if len > max {
+        return Err(ErrorPath::new(ReadError::SeqLengthViolation(max, len)));
+    }
+    decode::string_payload(r, buf, len)
+}
+
+/// Reads the payload of an array tag using `read_vec`, unless an element budget is being
+/// enforced. In that case, the length prefix is checked against the budget first, after which the
+/// elements are read one by one using `read_elem`.
+fn read_array<T, B: Read>(
+    buf: &mut B,
+    r: &impl Reader,
+    ctx: &mut ReadCtx,
+    read_vec: impl FnOnce(&mut B) -> decode::Res<Vec<T>>,
+    read_elem: impl Fn(&mut B) -> decode::Res<T>,
+) -> decode::Res<Vec<T>> {
+    if !ctx.has_element_budget() {
+        return read_vec(buf);
+    }
+    let len = r.i32(buf)?;
+    let len = usize::try_from(len).map_err(|_| {
+        ErrorPath::new(ReadError::SeqLengthViolation(
+            i32::MAX as usize,
+            len as usize,
+        ))
+    })?;
+    ctx.take_elements(len)?;
+    let mut vec = Vec::with_capacity(decode::prealloc_capacity::<T>(len));
+    for i in 0..len {
+        vec.push(read_elem(buf).map_err(|err| err.prepend(PathPart::Element(i)))?);
+    }
+    Ok(vec)
+}
+
 impl TagIo for tag::ByteArray {
-    fn read_payload(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
-        Ok(r.i8_vec(buf)?.into())
+    fn read_payload(buf: &mut impl Read, r: &impl Reader, ctx: &mut ReadCtx) -> decode::Res<Self> {
+        Ok(read_array(buf, r, ctx, |buf| r.i8_vec(buf), |buf| r.i8(buf))?.into())
     }
 
-    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
+    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer, _: &WriteOpts) -> encode::Res {
         w.write_i8_vec(buf, &self.0)
     }
 }
 impl TagIo for tag::IntArray {
-    fn read_payload(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
-        Ok(r.i32_vec(buf)?.into())
+    fn read_payload(buf: &mut impl Read, r: &impl Reader, ctx: &mut ReadCtx) -> decode::Res<Self> {
+        Ok(read_array(buf, r, ctx, |buf| r.i32_vec(buf), |buf| r.i32(buf))?.into())
     }
 
-    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
+    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer, _: &WriteOpts) -> encode::Res {
         w.write_i32_vec(buf, &self.0)
     }
 }
 impl TagIo for tag::LongArray {
-    fn read_payload(buf: &mut impl Read, r: &impl Reader) -> decode::Res<Self> {
-        Ok(r.i64_vec(buf)?.into())
+    fn read_payload(buf: &mut impl Read, r: &impl Reader, ctx: &mut ReadCtx) -> decode::Res<Self> {
+        Ok(read_array(buf, r, ctx, |buf| r.i64_vec(buf), |buf| r.i64(buf))?.into())
     }
 
-    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer) -> encode::Res {
+    fn write_payload(
+        &self,
+        buf: &mut impl Write,
+        w: &impl Writer,
+        opts: &WriteOpts,
+    ) -> encode::Res {
+        if opts.legacy_no_long_array {
+            return Err(ErrorPath::new(WriteError::UnsupportedTag(
+                NBTTagType::LongArray,
+            )));
+        }
         w.write_i64_vec(buf, &self.0)
     }
 }
+
+impl tag::Unknown {
+    /// Reads the payload of an unknown array-like tag, of which each element has the provided size
+    /// in bytes.
+    fn read_payload(
+        id: u8,
+        element_size: usize,
+        buf: &mut impl Read,
+        r: &impl Reader,
+        ctx: &mut ReadCtx,
+    ) -> decode::Res<Self> {
+        let len = r.i32(buf)?;
+        let byte_len = usize::try_from(len)
+            .ok()
+            .and_then(|len| len.checked_mul(element_size))
+            .ok_or_else(|| {
+                ErrorPath::new(ReadError::SeqLengthViolation(
+                    i32::MAX as usize,
+                    len as usize,
+                ))
+            })?;
+        ctx.take_elements(len as usize)?;
+        Ok(Self {
+            id,
+            len,
+            bytes: r.bytes(buf, byte_len)?,
+        })
+    }
+
+    fn write_payload(&self, buf: &mut impl Write, w: &impl Writer, _: &WriteOpts) -> encode::Res {
+        w.write_i32(buf, self.len)?;
+        buf.write_all(&self.bytes)
+            .map_err(|x| ErrorPath::new(x.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::BigEndian;
+    use crate::err::{PathPart, WriteError};
+    use crate::{tag, ArrayDirection, CanonOpts, NBTTag, NBTTagType, TypeNode};
+
+    #[test]
+    fn test_parse_tag_type() {
+        assert_eq!("Compound".parse(), Ok(NBTTagType::Compound));
+        assert_eq!("TAG_Compound".parse(), Ok(NBTTagType::Compound));
+        assert_eq!("TAG_Long_Array".parse(), Ok(NBTTagType::LongArray));
+        assert!("TAG_Unknown".parse::<NBTTagType>().is_err());
+        assert!("Unknown".parse::<NBTTagType>().is_err());
+        assert_eq!(NBTTagType::LongArray.to_string(), "LongArray");
+        assert_eq!(<&str>::from(NBTTagType::ByteArray), "ByteArray");
+    }
+
+    #[test]
+    fn test_unwrap_single() {
+        let list = NBTTag::List(tag::List::from([1i32]));
+        assert_eq!(list.unwrap_single(), Some(&NBTTag::from(1i32)));
+        let compound = NBTTag::Compound(tag::Compound::from([("a", "b")]));
+        assert_eq!(compound.unwrap_single(), Some(&NBTTag::from("b")));
+
+        assert_eq!(NBTTag::List(tag::List::default()).unwrap_single(), None);
+        assert_eq!(
+            NBTTag::List(tag::List::from([1i32, 2])).unwrap_single(),
+            None
+        );
+        assert_eq!(NBTTag::from(1i32).unwrap_single(), None);
+    }
+
+    #[test]
+    fn test_write_mixed_list() {
+        let nbt = NBTTag::List(tag::List::from(vec![NBTTag::from(1i32), NBTTag::from("a")]));
+        let mut buf = vec![];
+        let err = nbt.write(&mut buf, &BigEndian).unwrap_err();
+        assert!(matches!(
+            err.inner,
+            WriteError::UnexpectedTag(NBTTagType::Int, NBTTagType::String)
+        ));
+        assert_eq!(Vec::from(err.path.0), vec![PathPart::Element(1)]);
+    }
+
+    #[test]
+    fn test_nbt_get_path_mut() {
+        let mut nbt = NBTTag::List(tag::List::from([tag::Compound::builder()
+            .with_list(
+                "items",
+                vec![tag::Compound::builder().with_int("count", 1).build()],
+            )
+            .build()]));
+        *nbt.get_path_mut("[0].items[0].count").unwrap() = NBTTag::Int(tag::Int(64));
+        assert_eq!(
+            nbt.get_path_mut("[0].items[0].count").map(|v| &*v),
+            Some(&NBTTag::Int(tag::Int(64)))
+        );
+        if let Some(NBTTag::List(items)) = nbt.get_path_mut("[0].items") {
+            items.push(NBTTag::Compound(tag::Compound::default()));
+        }
+        assert!(nbt.get_path_mut("[0].items[1]").is_some());
+
+        assert!(nbt.get_path_mut("[1]").is_none());
+        assert!(nbt.get_path_mut("[0].items.count").is_none());
+        assert!(nbt.get_path_mut("[0].items[0].count.x").is_none());
+        assert!(nbt.get_path_mut("[").is_none());
+        assert_eq!(
+            nbt.get_path_mut("").map(|v| v.tag_type()),
+            Some(NBTTagType::List)
+        );
+    }
+
+    #[test]
+    fn test_type_tree() {
+        let nbt = nbt!({
+            "name": "Zuri",
+            "pos": [I; 0, 64, 0],
+            "items": [{"id": 1i16, "count": 2i8}, {"id": 3i16}],
+            "empty": [],
+        });
+        let TypeNode::Compound(tree) = nbt.type_tree() else {
+            panic!("expected a compound node");
+        };
+        assert_eq!(
+            tree.keys().collect::<Vec<_>>(),
+            ["name", "pos", "items", "empty"]
+        );
+        assert_eq!(tree["name"], TypeNode::Scalar(NBTTagType::String));
+        assert_eq!(tree["pos"], TypeNode::Scalar(NBTTagType::IntArray));
+        assert_eq!(
+            tree["items"],
+            TypeNode::List {
+                element: Some(Box::new(TypeNode::Compound(
+                    [
+                        ("id".to_string(), TypeNode::Scalar(NBTTagType::Short)),
+                        ("count".to_string(), TypeNode::Scalar(NBTTagType::Byte)),
+                    ]
+                    .into_iter()
+                    .collect()
+                ))),
+                len: 2,
+            }
+        );
+        assert_eq!(
+            tree["empty"],
+            TypeNode::List {
+                element: None,
+                len: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_truncate_strings() {
+        let mut nbt = NBTTag::List(tag::List::from([
+            tag::String::from("abcdef"),
+            tag::String::from("aé😀"),
+            tag::String::from("a\0b"),
+            tag::String::Bytes(vec![0xff; 8]),
+        ]));
+        nbt.truncate_strings(4);
+        let expected = NBTTag::List(tag::List::from([
+            tag::String::from("abcd"),
+            tag::String::from("aé"),
+            tag::String::from("a\0b"),
+            tag::String::Bytes(vec![0xff; 4]),
+        ]));
+        assert_eq!(nbt, expected);
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let mut a = nbt!({
+            "b": [{"y": 1i8, "x": f32::from_bits(0x7fc0_1234)}],
+            "a": [1i32, 2i32],
+        });
+        let mut b = nbt!({
+            "a": [1i32, 2i32],
+            "b": [{"x": f32::NAN, "y": 1i8}],
+        });
+        let opts = CanonOpts {
+            normalize_arrays: Some(ArrayDirection::ToArrays),
+        };
+        a.canonicalize(&opts);
+        b.canonicalize(&opts);
+
+        let (mut a_buf, mut b_buf) = (vec![], vec![]);
+        a.write(&mut a_buf, &BigEndian).unwrap();
+        b.write(&mut b_buf, &BigEndian).unwrap();
+        assert_eq!(a_buf, b_buf);
+        assert_eq!(a.view().at("a").get(), Some(&nbt!([I; 1, 2])));
+        let NBTTag::Compound(a) = a else {
+            panic!("expected a compound tag");
+        };
+        assert_eq!(a.keys().collect::<Vec<_>>(), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_normalize_arrays() {
+        let lists = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_list("bytes", vec![tag::Byte(1), tag::Byte(-1)])
+                .with_list("ints", vec![tag::Int(1)])
+                .with_list("longs", vec![tag::Long(i64::MIN)])
+                .with_list("empty", tag::List::default())
+                .with_list("strings", vec![tag::String::from("a")])
+                .build(),
+        );
+        let arrays = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_byte_array("bytes", vec![1, -1])
+                .with_int_array("ints", vec![1])
+                .with_long_array("longs", vec![i64::MIN])
+                .with_list("empty", tag::List::default())
+                .with_list("strings", vec![tag::String::from("a")])
+                .build(),
+        );
+
+        let mut nbt = lists.clone();
+        nbt.normalize_arrays(ArrayDirection::ToArrays);
+        assert_eq!(nbt, arrays);
+        nbt.normalize_arrays(ArrayDirection::ToLists);
+        assert_eq!(nbt, lists);
+    }
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(NBTTag::from(5i32).summary(), "Int(5)");
+        assert_eq!(NBTTag::from(1f32).summary(), "Float(1.0)");
+        assert_eq!(NBTTag::from("a\"b").summary(), r#"String("a\"b")"#);
+        assert_eq!(
+            NBTTag::from("a".repeat(100)).summary(),
+            format!("String({:?}...)", "a".repeat(64))
+        );
+        assert_eq!(
+            NBTTag::String(tag::String::Bytes(vec![b'a', 0xff])).summary(),
+            "String(\"a\u{fffd}\")"
+        );
+        assert_eq!(
+            NBTTag::Compound(tag::Compound::from([("a", 1i32), ("b", 2i32)])).summary(),
+            "Compound{2 keys}"
+        );
+        assert_eq!(
+            NBTTag::List(tag::List::from([1i8])).summary(),
+            "List<Byte>{1 element}"
+        );
+        assert_eq!(
+            NBTTag::List(tag::List::default()).summary(),
+            "List{0 elements}"
+        );
+        assert_eq!(
+            NBTTag::IntArray(tag::IntArray(vec![1, 2])).summary(),
+            "IntArray{2 elements}"
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let nbt = nbt!({
+            "a": 1i32,
+            "b": { "c": [[], [{ "d": [B; 1, 2] }]] },
+            "e": [1i8, 2i8, 3i8],
+        });
+        assert_eq!(nbt.max_depth(), 5);
+        assert_eq!(NBTTag::from(1i32).max_depth(), 0);
+        assert_eq!(NBTTag::Compound(tag::Compound::default()).max_depth(), 1);
+
+        let NBTTag::Compound(compound) = nbt else {
+            unreachable!()
+        };
+        assert_eq!(compound.entry_count_deep(), 11);
+        assert_eq!(tag::Compound::default().entry_count_deep(), 0);
+    }
+}
@@ -0,0 +1,194 @@
+//! Contains the [nbt!](crate::nbt) macro.
+
+/// Constructs an [NBTTag](crate::NBTTag) from a JSON-like literal.
+///
+/// Compounds are written as `{ "key": value, ... }` and lists as `[value, ...]`. Byte, int and long
+/// arrays are written as `[B; ...]`, `[I; ...]` and `[L; ...]` respectively. Any other value must
+/// be an expression that can be converted into an [NBTTag](crate::NBTTag) using [From], which
+/// means the type of number literals is determined by their suffix.
+///
+/// Panics when a compound contains the same key more than once.
+///
+/// Compounds and lists in which every value is a single token tree, such as a positive literal,
+/// a variable, a nested compound or list, or an expression in parentheses, or in which every value
+/// is a literal, including negative ones, are expanded all at once, so large literals only
+/// require recursion for their nesting. Other compounds and lists are expanded one entry at a
+/// time, so very large ones may require wrapping multi-token values in parentheses. Values that
+/// start with `-` but are not literals, such as `-x`, must always be written in parentheses.
+///
+/// # Usage
+/// ```
+/// # use zuri_nbt::{nbt, tag, NBTTag};
+/// let nbt = nbt!({
+///     "health": 20.0f32,
+///     "name": "Zuri",
+///     "items": [{ "id": 1i16, "count": 3i8 }, { "id": 2i16, "count": -1i8 }],
+///     "flags": [B; 1, 0, 1],
+///     "position": [I; 0, 64, -8],
+/// });
+/// assert_eq!(nbt.view().at("items").at(1).at("count").byte(), Ok(-1));
+/// assert_eq!(nbt.view().at("position").at(2).int(), Ok(-8));
+/// ```
+#[macro_export]
+macro_rules! nbt {
+    (@compound $builder:ident) => {};
+    (@compound $builder:ident $key:literal : $value:tt $(, $($rest:tt)*)?) => {
+        $builder = $builder.with($key, $crate::nbt!($value));
+        $crate::nbt!(@compound $builder $($($rest)*)?);
+    };
+    (@compound $builder:ident $key:literal : $value:expr $(, $($rest:tt)*)?) => {
+        $builder = $builder.with($key, $crate::nbt!($value));
+        $crate::nbt!(@compound $builder $($($rest)*)?);
+    };
+
+    (@list $list:ident) => {};
+    (@list $list:ident $value:tt $(, $($rest:tt)*)?) => {
+        $list.push($crate::nbt!($value));
+        $crate::nbt!(@list $list $($($rest)*)?);
+    };
+    (@list $list:ident $value:expr $(, $($rest:tt)*)?) => {
+        $list.push($crate::nbt!($value));
+        $crate::nbt!(@list $list $($($rest)*)?);
+    };
+
+    ({ $($key:literal : $value:tt),* $(,)? }) => {
+        $crate::NBTTag::Compound(
+            $crate::tag::Compound::builder()
+                $(.with($key, $crate::nbt!($value)))*
+                .build(),
+        )
+    };
+    ({ $($key:literal : $value:literal),* $(,)? }) => {
+        $crate::NBTTag::Compound(
+            $crate::tag::Compound::builder()
+                $(.with($key, $crate::NBTTag::from($value)))*
+                .build(),
+        )
+    };
+    ({ $($tt:tt)* }) => {{
+        let mut builder = $crate::tag::Compound::builder();
+        $crate::nbt!(@compound builder $($tt)*);
+        $crate::NBTTag::Compound(builder.build())
+    }};
+    ([B; $($value:expr),* $(,)?]) => {
+        $crate::NBTTag::ByteArray($crate::tag::ByteArray(vec![$($value),*]))
+    };
+    ([I; $($value:expr),* $(,)?]) => {
+        $crate::NBTTag::IntArray($crate::tag::IntArray(vec![$($value),*]))
+    };
+    ([L; $($value:expr),* $(,)?]) => {
+        $crate::NBTTag::LongArray($crate::tag::LongArray(vec![$($value),*]))
+    };
+    ([ $($value:tt),* $(,)? ]) => {
+        $crate::NBTTag::List($crate::tag::List::from(
+            ::std::vec::Vec::<$crate::NBTTag>::from([$($crate::nbt!($value)),*]),
+        ))
+    };
+    ([ $($value:literal),* $(,)? ]) => {
+        $crate::NBTTag::List($crate::tag::List::from(
+            ::std::vec::Vec::<$crate::NBTTag>::from([$($crate::NBTTag::from($value)),*]),
+        ))
+    };
+    ([ $($tt:tt)* ]) => {{
+        let mut list = $crate::tag::List::default();
+        $crate::nbt!(@list list $($tt)*);
+        $crate::NBTTag::List(list)
+    }};
+    ($value:expr) => {
+        $crate::NBTTag::from($value)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tag, NBTTag};
+
+    #[test]
+    fn test_nbt_macro() {
+        let nbt = nbt!({
+            "health": 20.0f32,
+            "items": [1i8, 2i8, -3i8],
+            "nested": { "empty": {}, "list": [], "longs": [L; -1, 2] },
+            "lists": [[1i32], [{ "a": "b" }]],
+        });
+        let expected = tag::Compound::builder()
+            .with_float("health", 20.0)
+            .with_list("items", vec![tag::Byte(1), tag::Byte(2), tag::Byte(-3)])
+            .with_compound(
+                "nested",
+                tag::Compound::builder()
+                    .with_compound("empty", tag::Compound::default())
+                    .with_list("list", tag::List::default())
+                    .with_long_array("longs", vec![-1, 2]),
+            )
+            .with_list(
+                "lists",
+                vec![
+                    tag::List::from(vec![tag::Int(1)]),
+                    tag::List::from(vec![tag::Compound::from([("a", "b")])]),
+                ],
+            );
+        assert_eq!(nbt, NBTTag::Compound(expected.build()));
+        assert_eq!(nbt!(-5i64), NBTTag::Long(tag::Long(-5)));
+    }
+
+    #[test]
+    fn test_nbt_macro_large() {
+        // Large literals must not be expanded one token or one entry at a time, which would exceed
+        // the default recursion limit.
+        let nbt = nbt!({
+            "k0": 0i8, "k1": -1i8, "k2": 2i8, "k3": -3i8, "k4": 4i8, "k5": -5i8, "k6": 6i8,
+            "k7": -7i8, "k8": 8i8, "k9": -9i8, "k10": 10i8, "k11": -11i8, "k12": 12i8, "k13": -13i8,
+            "k14": 14i8, "k15": -15i8, "k16": 16i8, "k17": -17i8, "k18": 18i8, "k19": -19i8,
+            "k20": 20i8, "k21": -21i8, "k22": 22i8, "k23": -23i8, "k24": 24i8, "k25": -25i8,
+            "k26": 26i8, "k27": -27i8, "k28": 28i8, "k29": -29i8, "k30": 30i8, "k31": -31i8,
+            "k32": 32i8, "k33": -33i8, "k34": 34i8, "k35": -35i8, "k36": 36i8, "k37": -37i8,
+            "k38": 38i8, "k39": -39i8, "k40": 40i8, "k41": -41i8, "k42": 42i8, "k43": -43i8,
+            "k44": 44i8, "k45": -45i8, "k46": 46i8, "k47": -47i8, "k48": 48i8, "k49": -49i8,
+            "k50": 50i8, "k51": -51i8, "k52": 52i8, "k53": -53i8, "k54": 54i8, "k55": -55i8,
+            "k56": 56i8, "k57": -57i8, "k58": 58i8, "k59": -59i8, "k60": 60i8, "k61": -61i8,
+            "k62": 62i8, "k63": -63i8, "k64": 64i8, "k65": -65i8, "k66": 66i8, "k67": -67i8,
+            "k68": 68i8, "k69": -69i8, "k70": 70i8, "k71": -71i8, "k72": 72i8, "k73": -73i8,
+            "k74": 74i8, "k75": -75i8, "k76": 76i8, "k77": -77i8, "k78": 78i8, "k79": -79i8,
+            "k80": 80i8, "k81": -81i8, "k82": 82i8, "k83": -83i8, "k84": 84i8, "k85": -85i8,
+            "k86": 86i8, "k87": -87i8, "k88": 88i8, "k89": -89i8, "k90": 90i8, "k91": -91i8,
+            "k92": 92i8, "k93": -93i8, "k94": 94i8, "k95": -95i8, "k96": 96i8, "k97": -97i8,
+            "k98": 98i8, "k99": -99i8, "k100": 100i8, "k101": -101i8, "k102": 102i8, "k103": -103i8,
+            "k104": 104i8, "k105": -105i8, "k106": 106i8, "k107": -107i8, "k108": 108i8,
+            "k109": -109i8, "k110": 110i8, "k111": -111i8, "k112": 112i8, "k113": -113i8,
+            "k114": 114i8, "k115": -115i8, "k116": 116i8, "k117": -117i8, "k118": 118i8,
+            "k119": -119i8
+        });
+        let NBTTag::Compound(compound) = &nbt else {
+            panic!("expected a compound");
+        };
+        assert_eq!(compound.len(), 120);
+        assert_eq!(compound.get("k0"), Some(&NBTTag::Byte(tag::Byte(0))));
+        assert_eq!(compound.get("k119"), Some(&NBTTag::Byte(tag::Byte(-119))));
+
+        let nbt = nbt!([
+            -0, -1, -2, -3, -4, -5, -6, -7, -8, -9, -10, -11, -12, -13, -14, -15, -16, -17, -18,
+            -19, -20, -21, -22, -23, -24, -25, -26, -27, -28, -29, -30, -31, -32, -33, -34, -35,
+            -36, -37, -38, -39, -40, -41, -42, -43, -44, -45, -46, -47, -48, -49, -50, -51, -52,
+            -53, -54, -55, -56, -57, -58, -59, -60, -61, -62, -63, -64, -65, -66, -67, -68, -69,
+            -70, -71, -72, -73, -74, -75, -76, -77, -78, -79, -80, -81, -82, -83, -84, -85, -86,
+            -87, -88, -89, -90, -91, -92, -93, -94, -95, -96, -97, -98, -99, -100, -101, -102,
+            -103, -104, -105, -106, -107, -108, -109, -110, -111, -112, -113, -114, -115, -116,
+            -117, -118, -119
+        ]);
+        let expected: Vec<_> = (0..120).map(|i| tag::Int(-i)).collect();
+        assert_eq!(nbt, NBTTag::List(expected.into()));
+
+        let x = 5;
+        assert_eq!(
+            nbt!({ "a": -1i8, "b": { "c": (-x) }, "d": [x, x + 1] }),
+            NBTTag::Compound(
+                tag::Compound::builder()
+                    .with_byte("a", -1)
+                    .with_compound("b", tag::Compound::builder().with_int("c", -5))
+                    .with_list("d", vec![tag::Int(5), tag::Int(6)])
+                    .build()
+            )
+        );
+    }
+}
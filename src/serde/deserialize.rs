@@ -1,9 +1,9 @@
 use crate::err::PathPart;
 use crate::serde::{DeserializeError, ErrorPath};
-use crate::NBTTag;
+use crate::{tag, NBTTag};
+use indexmap::{map, IndexMap};
 use serde::de;
 use serde::de::{DeserializeSeed, Visitor};
-use std::collections::{hash_map, HashMap};
 
 pub(super) struct Deserializer<'de> {
     nbt: &'de NBTTag,
@@ -23,7 +23,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
         V: Visitor<'de>,
     {
         match &self.nbt {
-            NBTTag::Byte(_) => self.deserialize_u8(visitor),
+            NBTTag::Byte(_) => self.deserialize_i8(visitor),
             NBTTag::Short(_) => self.deserialize_i16(visitor),
             NBTTag::Int(_) => self.deserialize_i32(visitor),
             NBTTag::Long(_) => self.deserialize_i64(visitor),
@@ -35,6 +35,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
             NBTTag::ByteArray(_) => self.deserialize_seq(visitor),
             NBTTag::IntArray(_) => self.deserialize_seq(visitor),
             NBTTag::LongArray(_) => self.deserialize_seq(visitor),
+            NBTTag::Unknown(_) => Err(ErrorPath::new(DeserializeError::UnexpectedTag)),
         }
     }
 
@@ -53,7 +54,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
         V: Visitor<'de>,
     {
         match self.nbt {
-            NBTTag::Byte(v) => visitor.visit_i8(v.0 as i8),
+            NBTTag::Byte(v) => visitor.visit_i8(v.0),
             _ => Err(ErrorPath::new(DeserializeError::UnexpectedTag)),
         }
     }
@@ -92,15 +93,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        if let NBTTag::ByteArray(v) = self.nbt {
-            visitor.visit_i128(u128::from_le_bytes(
-                v.0[0..std::mem::size_of::<i128>()]
-                    .try_into()
-                    .map_err(|_| ErrorPath::new(DeserializeError::InvalidConversion))?,
-            ) as i128)
-        } else {
-            Err(ErrorPath::new(DeserializeError::UnexpectedTag))
-        }
+        visitor.visit_i128(i128::from_le_bytes(int_bytes(self.nbt)?))
     }
 
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -108,7 +101,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
         V: Visitor<'de>,
     {
         match self.nbt {
-            NBTTag::Byte(v) => visitor.visit_u8(v.0),
+            NBTTag::Byte(v) => visitor.visit_u8(v.0 as u8),
             _ => Err(ErrorPath::new(DeserializeError::UnexpectedTag)),
         }
     }
@@ -147,15 +140,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        if let NBTTag::ByteArray(v) = self.nbt {
-            visitor.visit_u128(u128::from_le_bytes(
-                v.0[0..std::mem::size_of::<u128>()]
-                    .try_into()
-                    .map_err(|_| ErrorPath::new(DeserializeError::InvalidConversion))?,
-            ))
-        } else {
-            Err(ErrorPath::new(DeserializeError::UnexpectedTag))
-        }
+        visitor.visit_u128(u128::from_le_bytes(int_bytes(self.nbt)?))
     }
 
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -196,7 +181,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
         V: Visitor<'de>,
     {
         match self.nbt {
-            NBTTag::String(v) => visitor.visit_borrowed_str(v.0.as_str()),
+            NBTTag::String(v) => visitor.visit_borrowed_str(utf8(v)?),
             _ => Err(ErrorPath::new(DeserializeError::UnexpectedTag)),
         }
     }
@@ -206,7 +191,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
         V: Visitor<'de>,
     {
         match self.nbt {
-            NBTTag::String(v) => visitor.visit_borrowed_str(v.0.as_str()),
+            NBTTag::String(v) => visitor.visit_borrowed_str(utf8(v)?),
             _ => Err(ErrorPath::new(DeserializeError::UnexpectedTag)),
         }
     }
@@ -215,10 +200,8 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        match self.nbt {
-            NBTTag::ByteArray(v) => visitor.visit_borrowed_bytes(v.0.as_slice()),
-            _ => Err(ErrorPath::new(DeserializeError::UnexpectedTag)),
-        }
+        // Byte arrays store signed bytes, so they cannot be borrowed as a `&[u8]`.
+        self.deserialize_byte_buf(visitor)
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -226,7 +209,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
         V: Visitor<'de>,
     {
         match self.nbt {
-            NBTTag::ByteArray(v) => visitor.visit_byte_buf(v.0.clone()),
+            NBTTag::ByteArray(v) => visitor.visit_byte_buf(v.iter_u8().collect()),
             _ => Err(ErrorPath::new(DeserializeError::UnexpectedTag)),
         }
     }
@@ -240,8 +223,8 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
                 .0
                 .get("variant")
                 .ok_or(ErrorPath::new(DeserializeError::UnexpectedVariant))?;
-            let variant = if let NBTTag::String(v) = variant {
-                v.0.as_str()
+            let variant = if let NBTTag::String(tag::String::Utf8(v)) = variant {
+                v.as_str()
             } else {
                 return Err(ErrorPath::new(DeserializeError::UnexpectedVariant));
             };
@@ -389,7 +372,7 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
         V: Visitor<'de>,
     {
         match self.nbt {
-            NBTTag::String(v) => visitor.visit_str(v.0.as_str()),
+            NBTTag::String(v) => visitor.visit_str(utf8(v)?),
             _ => Err(ErrorPath::new(DeserializeError::UnexpectedTag)),
         }
     }
@@ -402,6 +385,25 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     }
 }
 
+/// Returns the string as a `&str`, or an error if it is not valid UTF-8.
+fn utf8(v: &tag::String) -> Result<&str, ErrorPath<DeserializeError>> {
+    match v {
+        tag::String::Utf8(s) => Ok(s.as_str()),
+        tag::String::Bytes(_) => Err(ErrorPath::new(DeserializeError::InvalidConversion)),
+    }
+}
+
+/// Returns the bytes of a 128-bit integer, which is stored as a byte array of 16 bytes.
+fn int_bytes(nbt: &NBTTag) -> Result<[u8; 16], ErrorPath<DeserializeError>> {
+    let NBTTag::ByteArray(v) = nbt else {
+        return Err(ErrorPath::new(DeserializeError::UnexpectedTag));
+    };
+    v.iter_u8()
+        .collect::<Vec<_>>()
+        .try_into()
+        .map_err(|_| ErrorPath::new(DeserializeError::InvalidConversion))
+}
+
 impl<'de> de::VariantAccess<'de> for Deserializer<'de> {
     type Error = ErrorPath<DeserializeError>;
 
@@ -436,7 +438,7 @@ impl<'de> de::VariantAccess<'de> for Deserializer<'de> {
 }
 
 struct EnumAccess<'de> {
-    map: &'de HashMap<String, NBTTag>,
+    map: &'de IndexMap<String, NBTTag>,
 }
 
 impl<'de> de::EnumAccess<'de> for EnumAccess<'de> {
@@ -490,7 +492,7 @@ impl<'de, I: Iterator<Item = &'de NBTTag>> de::SeqAccess<'de> for ListAccess<'de
 }
 
 struct TupleAccess<'de> {
-    map: &'de HashMap<String, NBTTag>,
+    map: &'de IndexMap<String, NBTTag>,
     next: usize,
 }
 
@@ -518,7 +520,7 @@ impl<'de> de::SeqAccess<'de> for TupleAccess<'de> {
 }
 
 struct CompoundAccess<'de> {
-    map_iter: hash_map::Iter<'de, String, NBTTag>,
+    map_iter: map::Iter<'de, String, NBTTag>,
     next_value: Option<(&'de str, &'de NBTTag)>,
 }
 
@@ -1,5 +1,6 @@
 //! Enables the serialization and deserialization of rust types into NBT data through the
-//! [serialize] and [deserialize] functions respectively.
+//! [serialize] and [deserialize] functions respectively. Stateful deserialization, for example to
+//! reuse allocations across many values, is supported through [deserialize_seed].
 //!
 //! ## Example
 //! To be able to deserialize and serialize your types, you will need to derive
@@ -30,6 +31,7 @@ use crate::err::ErrorPath;
 use crate::serde::deserialize::Deserializer;
 use crate::serde::serialize::Serializer;
 use crate::NBTTag;
+use serde::de::DeserializeSeed;
 use serde::{de, ser, Deserialize, Serialize};
 use std::fmt::Display;
 use thiserror::Error;
@@ -62,6 +64,18 @@ pub fn deserialize<'de, T: Deserialize<'de>>(
     T::deserialize(Deserializer::<'de>::new(input))
 }
 
+/// Deserialize NBT data using a [DeserializeSeed], which carries state into the deserialization.
+///
+/// This makes it possible to reuse existing allocations when deserializing many similar values,
+/// for example by using a seed that deserializes a list by appending to an existing [Vec], instead
+/// of allocating a new one for every value.
+pub fn deserialize_seed<'de, S: DeserializeSeed<'de>>(
+    seed: S,
+    input: &'de NBTTag,
+) -> Result<S::Value, ErrorPath<DeserializeError>> {
+    seed.deserialize(Deserializer::<'de>::new(input))
+}
+
 /// An error that can occur when deserializing data.
 ///
 /// Generally, this indicates that the input data is invalid.
@@ -129,10 +143,12 @@ impl ser::Error for SerializeError {
 
 #[cfg(test)]
 mod tests {
-    use crate::serde::{deserialize, serialize};
+    use crate::serde::{deserialize, deserialize_seed, serialize};
     use crate::{tag, NBTTag};
-    use serde::{Deserialize, Serialize};
+    use serde::de::{DeserializeSeed, SeqAccess, Visitor};
+    use serde::{Deserialize, Deserializer, Serialize};
     use std::collections::HashMap;
+    use std::fmt::Formatter;
 
     #[derive(Serialize, Deserialize, Debug, PartialEq)]
     pub struct ExampleStruct<'a> {
@@ -231,4 +247,43 @@ mod tests {
             serialize(&input).unwrap_or_else(|err| panic!("Could not serialize: {}", err));
         assert_eq!(serialized, output);
     }
+
+    /// Deserializes a list of ints by appending them to an existing [Vec].
+    struct ExtendVec<'a>(&'a mut Vec<i32>);
+
+    impl<'de> DeserializeSeed<'de> for ExtendVec<'_> {
+        type Value = ();
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+            deserializer.deserialize_seq(self)
+        }
+    }
+
+    impl<'de> Visitor<'de> for ExtendVec<'_> {
+        type Value = ();
+
+        fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+            f.write_str("a list of ints")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+            while let Some(v) = seq.next_element()? {
+                self.0.push(v);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_deserialize_seed() {
+        let mut buf = Vec::with_capacity(16);
+        for input in [vec![1, 2, 3], vec![4, 5]] {
+            buf.clear();
+            let nbt = NBTTag::IntArray(input.clone().into());
+            deserialize_seed(ExtendVec(&mut buf), &nbt).unwrap();
+            assert_eq!(buf, input);
+            assert_eq!(buf.capacity(), 16);
+        }
+        assert!(deserialize_seed(ExtendVec(&mut buf), &NBTTag::from(1i32)).is_err());
+    }
 }
@@ -1,13 +1,13 @@
 use crate::err::{ErrorPath, PathPart};
 use crate::serde::SerializeError;
 use crate::{err, tag, NBTTag};
+use indexmap::IndexMap;
 use serde::{ser, Serialize};
-use std::collections::HashMap;
 
 pub(super) struct Serializer;
 
 fn wrap_enum(variant: &str, value: NBTTag) -> NBTTag {
-    let mut map = HashMap::new();
+    let mut map = IndexMap::new();
     map.insert(
         "variant".to_string(),
         NBTTag::String(variant.to_string().into()),
@@ -29,11 +29,11 @@ impl ser::Serializer for Serializer {
     type SerializeStructVariant = CompoundVariantSerializer;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
-        Ok(NBTTag::Byte((v as u8).into()))
+        Ok(NBTTag::Byte((v as i8).into()))
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
-        Ok(NBTTag::Byte((v as u8).into()))
+        Ok(NBTTag::Byte(v.into()))
     }
 
     fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
@@ -49,7 +49,7 @@ impl ser::Serializer for Serializer {
     }
 
     fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
-        Ok(NBTTag::Byte(v.into()))
+        Ok(NBTTag::Byte((v as i8).into()))
     }
 
     fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
@@ -77,7 +77,9 @@ impl ser::Serializer for Serializer {
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        Ok(NBTTag::ByteArray(v.to_vec().into()))
+        let mut array = tag::ByteArray::default();
+        array.extend_from_u8_slice(v);
+        Ok(NBTTag::ByteArray(array))
     }
 
     fn serialize_struct(
@@ -93,11 +95,11 @@ impl ser::Serializer for Serializer {
     }
 
     fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
-        Ok(NBTTag::ByteArray((v as u128).to_le_bytes().to_vec().into()))
+        self.serialize_bytes(&v.to_le_bytes())
     }
 
     fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
-        Ok(NBTTag::ByteArray(v.to_le_bytes().to_vec().into()))
+        self.serialize_bytes(&v.to_le_bytes())
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
@@ -108,9 +110,9 @@ impl ser::Serializer for Serializer {
         Ok(wrap_enum("None", NBTTag::Compound(Default::default())))
     }
 
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         Ok(wrap_enum("Some", value.serialize(Serializer)?))
     }
@@ -132,18 +134,18 @@ impl ser::Serializer for Serializer {
         Ok(wrap_enum(variant, NBTTag::Compound(Default::default())))
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
         _name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(Serializer)
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -151,7 +153,7 @@ impl ser::Serializer for Serializer {
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         Ok(wrap_enum(variant, value.serialize(Serializer)?))
     }
@@ -281,7 +283,7 @@ impl ser::Serializer for Serializer {
 /// Helper to serialize certain data types into a [NBTTag::Compound].
 #[derive(Default)]
 pub(super) struct CompoundSerializer {
-    v: HashMap<String, NBTTag>,
+    v: IndexMap<String, NBTTag>,
     index: usize,
 }
 
@@ -289,13 +291,9 @@ impl ser::SerializeStruct for CompoundSerializer {
     type Ok = <Serializer as ser::Serializer>::Ok;
     type Error = <Serializer as ser::Serializer>::Error;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         self.v.insert(
             key.to_string(),
@@ -315,45 +313,33 @@ impl ser::SerializeMap for CompoundSerializer {
     type Ok = <Serializer as ser::Serializer>::Ok;
     type Error = <Serializer as ser::Serializer>::Error;
 
-    fn serialize_key<T: ?Sized>(&mut self, _key: &T) -> Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, _key: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unreachable!()
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, _value: &T) -> Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, _value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         unreachable!()
     }
 
-    fn serialize_entry<K: ?Sized, V: ?Sized>(
-        &mut self,
-        key: &K,
-        value: &V,
-    ) -> Result<(), Self::Error>
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Self::Error>
     where
-        K: Serialize,
-        V: Serialize,
+        K: ?Sized + Serialize,
+        V: ?Sized + Serialize,
     {
-        let key_str = if let NBTTag::String(str) = key.serialize(Serializer)? {
-            str
-        } else {
-            return Err(ErrorPath::new(SerializeError::NonStringKey));
+        let key = match key.serialize(Serializer)? {
+            NBTTag::String(tag::String::Utf8(key)) => key,
+            _ => return Err(ErrorPath::new(SerializeError::NonStringKey)),
         };
-        self.v.insert(
-            key_str.0,
-            value.serialize(Serializer).map_err(|err| {
-                err.prepend(PathPart::MapKey(
-                    // The key has moved into the map, so we need to serialize it again.
-                    <NBTTag as TryInto<tag::String>>::try_into(key.serialize(Serializer).unwrap())
-                        .unwrap()
-                        .0,
-                ))
-            })?,
-        );
+        let value = value
+            .serialize(Serializer)
+            .map_err(|err| err.prepend(PathPart::MapKey(key.clone())))?;
+        self.v.insert(key, value);
         Ok(())
     }
 
@@ -366,9 +352,9 @@ impl ser::SerializeTuple for CompoundSerializer {
     type Ok = <Serializer as ser::Serializer>::Ok;
     type Error = <Serializer as ser::Serializer>::Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         self.v.insert(
             format!("{}", self.index),
@@ -389,9 +375,9 @@ impl ser::SerializeTupleStruct for CompoundSerializer {
     type Ok = <Serializer as ser::Serializer>::Ok;
     type Error = <Serializer as ser::Serializer>::Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         <Self as ser::SerializeTuple>::serialize_element(self, value)
     }
@@ -421,9 +407,9 @@ impl ser::SerializeTupleVariant for CompoundVariantSerializer {
     type Ok = <Serializer as ser::Serializer>::Ok;
     type Error = <Serializer as ser::Serializer>::Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         <CompoundSerializer as ser::SerializeTuple>::serialize_element(&mut self.inner, value)
     }
@@ -440,13 +426,9 @@ impl ser::SerializeStructVariant for CompoundVariantSerializer {
     type Ok = <Serializer as ser::Serializer>::Ok;
     type Error = <Serializer as ser::Serializer>::Error;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         <CompoundSerializer as ser::SerializeStruct>::serialize_field(&mut self.inner, key, value)
     }
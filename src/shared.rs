@@ -0,0 +1,82 @@
+//! See [SharedTag].
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::NBTTag;
+
+/// A cheaply cloneable, reference counted [NBTTag] with copy-on-write semantics.
+///
+/// Cloning a shared tag only increments a reference count, which makes it suitable for templates
+/// that are instantiated many times, such as a prototype entity. The underlying tag is only cloned
+/// once it is mutated through [SharedTag::make_mut] while other clones still exist.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedTag(Arc<NBTTag>);
+
+impl SharedTag {
+    /// Creates a new shared tag from an NBT tag.
+    pub fn new(tag: NBTTag) -> Self {
+        Self(Arc::new(tag))
+    }
+
+    /// Returns a mutable reference to the underlying NBT tag, cloning it first if it is shared
+    /// with other clones of this shared tag.
+    ///
+    /// See [Arc::make_mut].
+    pub fn make_mut(&mut self) -> &mut NBTTag {
+        Arc::make_mut(&mut self.0)
+    }
+
+    /// Returns true if both shared tags point to the same allocation.
+    ///
+    /// This is a cheap identity check that can be used to short-circuit a deep equality check, for
+    /// example in caches. Two shared tags may still be equal when this returns false.
+    pub fn same_arc(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Returns the underlying NBT tag, cloning it if it is shared with other clones of this shared
+    /// tag.
+    pub fn into_inner(self) -> NBTTag {
+        Arc::unwrap_or_clone(self.0)
+    }
+}
+
+impl Deref for SharedTag {
+    type Target = NBTTag;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<NBTTag> for SharedTag {
+    fn from(value: NBTTag) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<SharedTag> for NBTTag {
+    fn from(value: SharedTag) -> Self {
+        value.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{tag, NBTTag};
+
+    #[test]
+    fn test_copy_on_write() {
+        let template =
+            NBTTag::Compound(tag::Compound::builder().with_int("health", 20).build()).shared();
+        let mut instance = template.clone();
+        assert!(template.same_arc(&instance));
+
+        if let NBTTag::Compound(c) = instance.make_mut() {
+            c.insert("id".to_string(), NBTTag::Int(1.into()));
+        }
+        assert!(!template.same_arc(&instance));
+        assert_eq!(template.view().at("id").get(), None);
+        assert_eq!(instance.view().at("id").int(), Ok(1));
+    }
+}
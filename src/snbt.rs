@@ -0,0 +1,906 @@
+//! Support for stringified NBT (SNBT), the textual representation of NBT used by Minecraft: Java
+//! Edition commands. See [NBTTag::to_snbt] and [NBTTag::from_snbt].
+use std::fmt::{Display, Formatter, Write};
+use std::str::FromStr;
+
+use crate::err::SnbtError;
+use crate::{tag, NBTTag};
+
+/// The maximum depth to which compound and list tags may be nested in parsed SNBT, which is the
+/// same limit that Minecraft uses.
+const MAX_DEPTH: usize = 512;
+
+/// Options that alter how NBT data is written as SNBT using [NBTTag::to_snbt_with], or parsed from
+/// SNBT using [NBTTag::from_snbt_with].
+#[derive(Debug, Default, Clone)]
+pub struct SnbtOpts {
+    /// Determines how float and double tags are written.
+    pub float_format: FloatFormat,
+    /// Whether the entries of compound tags are written in sorted order of their keys, rather
+    /// than in the iteration order of the compound. This makes the output reproducible regardless
+    /// of how the compound was constructed.
+    pub sort_keys: bool,
+    /// The number of spaces by which nested entries are indented, or `None` to write everything on
+    /// a single line.
+    ///
+    /// When set, every entry of a compound or list tag is written on a separate line, and a space
+    /// is written after the colon following each compound key. Arrays are always written on a
+    /// single line.
+    pub indent: Option<usize>,
+    /// Whether numbers without a type suffix are read leniently when parsing, which is useful for
+    /// hand-typed SNBT. Has no effect on writing.
+    ///
+    /// In lenient mode, an integer without a suffix is read as the smallest integer tag it fits
+    /// in, rather than always as an int. A list that only contains such integers is read as the
+    /// narrowest array tag that fits all of them, as if the array prefix had been written, and the
+    /// elements of typed arrays may be any integer without a suffix that fits the element type.
+    /// Decimal numbers without a suffix are read as a double in either mode.
+    pub lenient: bool,
+}
+
+/// Determines how float and double tags are written as SNBT.
+///
+/// In both formats, the digits are the shortest representation that reads back as the exact same
+/// value, so the output for a given value never varies.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum FloatFormat {
+    /// Always write a decimal point and a type suffix, such as `1.0f`, `0.5d` and `1.0e30d`. For
+    /// finite values, this is the format accepted by all versions of Minecraft.
+    #[default]
+    Explicit,
+    /// Write the shortest text that still identifies the type, such as `1f`, `0.5` and `1e30d`.
+    /// Doubles are only written without a suffix if they contain a decimal point.
+    Shortest,
+}
+
+impl NBTTag {
+    /// Writes the NBT tag as an SNBT string, such as `{name:"Zuri",pos:[I;0,64,0]}`, using the
+    /// default [SnbtOpts].
+    ///
+    /// See [Self::to_snbt_with] for the values that Minecraft cannot read back.
+    pub fn to_snbt(&self) -> String {
+        self.to_snbt_with(&SnbtOpts::default())
+    }
+
+    /// Writes the NBT tag as an indented, multi-line SNBT string that is easier to read, using an
+    /// indent of four spaces. See [SnbtOpts::indent], and [Self::to_snbt_with] for the values that
+    /// Minecraft cannot read back.
+    pub fn to_snbt_pretty(&self) -> String {
+        self.to_snbt_with(&SnbtOpts {
+            indent: Some(4),
+            ..Default::default()
+        })
+    }
+
+    /// Writes the NBT tag as an SNBT string using the provided [SnbtOpts].
+    ///
+    /// Unknown tags cannot be represented in SNBT, and are written as a byte array containing
+    /// their raw payload instead.
+    ///
+    /// # Compatibility
+    /// The output is only accepted by Minecraft if every float and double tag is finite. SNBT has
+    /// no syntax for NaN and infinite values, so they are written as `NaN`, `inf` and `-inf`
+    /// followed by the type suffix, which only [Self::from_snbt] reads back as numbers. Use
+    /// [crate::validate::ValidateOpts::reject_non_finite] to check for such values beforehand.
+    ///
+    /// Strings that are not valid UTF-8, which are stored as [tag::String::Bytes], are written
+    /// lossily with any invalid sequences replaced by `U+FFFD`, so they do not read back as the
+    /// same bytes.
+    pub fn to_snbt_with(&self, opts: &SnbtOpts) -> String {
+        let mut out = String::new();
+        write_tag(&mut out, self, opts, 0);
+        out
+    }
+}
+
+impl NBTTag {
+    /// Parses an SNBT string, such as `{name:"Zuri",pos:[I;0,64,0]}`, into an NBT tag.
+    ///
+    /// Numbers use the same type suffixes as Minecraft: `b`, `s`, `L`, `f` and `d`, in either
+    /// case. Numbers without a suffix are read as an int, or as a double if they contain a
+    /// decimal point or exponent, and `true` and `false` are read as the bytes 1 and 0. Any other
+    /// unquoted text is read as a string. All elements of a list must have the same type, and the
+    /// elements of typed arrays such as `[B;1b,2b]` must have the element type of the array.
+    ///
+    /// This is the inverse of [Self::to_snbt], apart from unknown tags, which are written as byte
+    /// arrays.
+    pub fn from_snbt(s: &str) -> Result<NBTTag, SnbtError> {
+        Self::from_snbt_with(s, &SnbtOpts::default())
+    }
+
+    /// Parses an SNBT string into an NBT tag using the provided [SnbtOpts]. See
+    /// [SnbtOpts::lenient] for how numbers can be read more leniently than with
+    /// [Self::from_snbt].
+    pub fn from_snbt_with(s: &str, opts: &SnbtOpts) -> Result<NBTTag, SnbtError> {
+        let mut parser = Parser {
+            input: s,
+            pos: 0,
+            depth: 0,
+            lenient: opts.lenient,
+        };
+        let tag = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos < s.len() {
+            return Err(SnbtError::TrailingInput(parser.pos));
+        }
+        Ok(tag)
+    }
+}
+
+/// Writes the tag as SNBT using [NBTTag::to_snbt], or using [NBTTag::to_snbt_pretty] if the
+/// alternate flag (`{:#}`) is used.
+impl Display for NBTTag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_snbt(f, |out, opts| write_tag(out, self, opts, 0))
+    }
+}
+
+/// Writes the compound as SNBT in the same way as [NBTTag]'s [Display] implementation.
+impl Display for tag::Compound {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_snbt(f, |out, opts| write_compound(out, self, opts, 0))
+    }
+}
+
+/// Writes the list as SNBT in the same way as [NBTTag]'s [Display] implementation.
+impl Display for tag::List {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_snbt(f, |out, opts| write_list(out, self, opts, 0))
+    }
+}
+
+/// Writes the array as SNBT, such as `[B;1b,2b,3b]`.
+impl Display for tag::ByteArray {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_snbt(f, |out, _| write_byte_array(out, self))
+    }
+}
+
+/// Writes the array as SNBT, such as `[I;1,2,3]`.
+impl Display for tag::IntArray {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_snbt(f, |out, _| write_int_array(out, self))
+    }
+}
+
+/// Writes the array as SNBT, such as `[L;1L,2L,3L]`.
+impl Display for tag::LongArray {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        fmt_snbt(f, |out, _| write_long_array(out, self))
+    }
+}
+
+/// Writes SNBT produced by `write` to the formatter, using the default [SnbtOpts] or the options of
+/// [NBTTag::to_snbt_pretty] if the alternate flag is used.
+fn fmt_snbt(f: &mut Formatter<'_>, write: impl FnOnce(&mut String, &SnbtOpts)) -> std::fmt::Result {
+    let opts = SnbtOpts {
+        indent: f.alternate().then_some(4),
+        ..Default::default()
+    };
+    let mut out = String::new();
+    write(&mut out, &opts);
+    f.write_str(&out)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    depth: usize,
+    lenient: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    /// Returns an error for the current position, describing what was expected there.
+    fn expected(&self, expected: &'static str) -> SnbtError {
+        if self.pos >= self.input.len() {
+            SnbtError::UnexpectedEnd(self.pos)
+        } else {
+            SnbtError::Expected {
+                offset: self.pos,
+                expected,
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<NBTTag, SnbtError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.nested(Self::compound),
+            Some('[') => self.nested(Self::list_or_array),
+            Some('"' | '\'') => Ok(NBTTag::String(self.quoted()?.into())),
+            _ => {
+                let offset = self.pos;
+                let token = self.unquoted();
+                if token.is_empty() {
+                    return Err(self.expected("a value"));
+                }
+                parse_scalar(token, offset, self.lenient)
+            }
+        }
+    }
+
+    /// Parses a compound or list tag using `f`, checking that the maximum depth is not exceeded.
+    fn nested(
+        &mut self,
+        f: fn(&mut Self) -> Result<NBTTag, SnbtError>,
+    ) -> Result<NBTTag, SnbtError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(SnbtError::DepthLimitExceeded(self.pos));
+        }
+        self.depth += 1;
+        let tag = f(self);
+        self.depth -= 1;
+        tag
+    }
+
+    fn compound(&mut self) -> Result<NBTTag, SnbtError> {
+        self.pos += 1;
+        let mut compound = tag::Compound::default();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(NBTTag::Compound(compound));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = match self.peek() {
+                Some('"' | '\'') => self.quoted()?,
+                _ => match self.unquoted() {
+                    "" => return Err(self.expected("a key")),
+                    key => key.to_string(),
+                },
+            };
+            self.skip_whitespace();
+            if self.peek() != Some(':') {
+                return Err(self.expected("`:`"));
+            }
+            self.pos += 1;
+            let value = self.value()?;
+            compound.insert(key, value);
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(NBTTag::Compound(compound));
+                }
+                _ => return Err(self.expected("`,` or `}`")),
+            }
+        }
+    }
+
+    fn list_or_array(&mut self) -> Result<NBTTag, SnbtError> {
+        self.pos += 1;
+        let mut array = match self.input.as_bytes()[self.pos..] {
+            [b'B', b';', ..] => NBTTag::ByteArray(Default::default()),
+            [b'I', b';', ..] => NBTTag::IntArray(Default::default()),
+            [b'L', b';', ..] => NBTTag::LongArray(Default::default()),
+            _ => {
+                let mut elements = Vec::new();
+                self.elements(|offset, bare, value| {
+                    elements.push((offset, bare, value));
+                    Ok(())
+                })?;
+                if self.lenient && !elements.is_empty() && elements.iter().all(|e| e.1) {
+                    return Ok(infer_array(elements.iter().map(|e| integer_value(&e.2))));
+                }
+                let mut list = tag::List::default();
+                for (offset, _, value) in elements {
+                    match list.first() {
+                        Some(first) if first.tag_type() != value.tag_type() => {
+                            return Err(SnbtError::MixedList(offset))
+                        }
+                        _ => list.push(value),
+                    }
+                }
+                return Ok(NBTTag::List(list));
+            }
+        };
+
+        self.pos += 2;
+        let lenient = self.lenient;
+        self.elements(|offset, bare, value| {
+            let err = || SnbtError::InvalidArrayElement(offset);
+            match (&mut array, value) {
+                (NBTTag::ByteArray(a), NBTTag::Byte(v)) => a.0.push(v.0),
+                (NBTTag::IntArray(a), NBTTag::Int(v)) => a.0.push(v.0),
+                (NBTTag::LongArray(a), NBTTag::Long(v)) => a.0.push(v.0),
+                (NBTTag::ByteArray(a), v) if lenient && bare => {
+                    a.0.push(integer_value(&v).try_into().map_err(|_| err())?)
+                }
+                (NBTTag::IntArray(a), v) if lenient && bare => {
+                    a.0.push(integer_value(&v).try_into().map_err(|_| err())?)
+                }
+                (NBTTag::LongArray(a), v) if lenient && bare => a.0.push(integer_value(&v)),
+                _ => return Err(err()),
+            }
+            Ok(())
+        })?;
+        Ok(array)
+    }
+
+    /// Parses the comma-separated elements of a list or array up to and including the closing
+    /// bracket, passing each element to `f` along with its offset and whether it is an integer
+    /// without a type suffix.
+    fn elements(
+        &mut self,
+        mut f: impl FnMut(usize, bool, NBTTag) -> Result<(), SnbtError>,
+    ) -> Result<(), SnbtError> {
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(());
+        }
+        loop {
+            self.skip_whitespace();
+            let offset = self.pos;
+            let value = self.value()?;
+            f(offset, is_integer(&self.input[offset..self.pos]), value)?;
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => return Err(self.expected("`,` or `]`")),
+            }
+        }
+    }
+
+    /// Parses a string enclosed in single or double quotes, in which a backslash escapes the next
+    /// backslash or quote.
+    fn quoted(&mut self) -> Result<String, SnbtError> {
+        let quote = self.peek();
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(SnbtError::UnexpectedEnd(self.pos));
+            };
+            self.pos += c.len_utf8();
+            match c {
+                '\\' => match self.peek() {
+                    Some(c @ ('\\' | '"' | '\'')) => {
+                        s.push(c);
+                        self.pos += 1;
+                    }
+                    _ => return Err(SnbtError::InvalidEscape(self.pos - 1)),
+                },
+                c if Some(c) == quote => return Ok(s),
+                c => s.push(c),
+            }
+        }
+    }
+
+    /// Reads the longest run of characters that are allowed in unquoted strings and keys.
+    fn unquoted(&mut self) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek().filter(|c| is_unquoted_char(*c)) {
+            self.pos += c.len_utf8();
+        }
+        &self.input[start..self.pos]
+    }
+}
+
+fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+/// Returns whether the text is an integer without a type suffix.
+fn is_integer(s: &str) -> bool {
+    let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Returns the value of an integer tag that was read from an integer without a type suffix.
+fn integer_value(tag: &NBTTag) -> i64 {
+    match tag {
+        NBTTag::Byte(v) => v.0.into(),
+        NBTTag::Short(v) => v.0.into(),
+        NBTTag::Int(v) => v.0.into(),
+        NBTTag::Long(v) => v.0,
+        _ => unreachable!("integers without a suffix are always read as integer tags"),
+    }
+}
+
+/// Reads a list of integers without type suffixes as the narrowest array tag that fits all values.
+fn infer_array(values: impl Iterator<Item = i64> + Clone) -> NBTTag {
+    if let Ok(v) = values.clone().map(i8::try_from).collect() {
+        NBTTag::ByteArray(tag::ByteArray(v))
+    } else if let Ok(v) = values.clone().map(i32::try_from).collect() {
+        NBTTag::IntArray(tag::IntArray(v))
+    } else {
+        NBTTag::LongArray(tag::LongArray(values.collect()))
+    }
+}
+
+/// Parses an unquoted value, which is a number if it has the form of one and a string otherwise.
+///
+/// In lenient mode, integers without a suffix are read as the smallest integer tag they fit in.
+fn parse_scalar(token: &str, offset: usize, lenient: bool) -> Result<NBTTag, SnbtError> {
+    fn is_float(s: &str, allow_special: bool) -> bool {
+        s.parse::<f64>().is_ok()
+            && (s.bytes().any(|b| b.is_ascii_digit())
+                || allow_special
+                    && matches!(
+                        s.to_ascii_lowercase().as_str(),
+                        "nan" | "inf" | "+inf" | "-inf"
+                    ))
+    }
+
+    fn number<T: FromStr>(s: &str, offset: usize) -> Result<T, SnbtError> {
+        s.parse().map_err(|_| SnbtError::InvalidNumber(offset))
+    }
+
+    match token {
+        "true" => return Ok(NBTTag::Byte(tag::Byte(1))),
+        "false" => return Ok(NBTTag::Byte(tag::Byte(0))),
+        _ if is_integer(token) && lenient => {
+            let v: i64 = number(token, offset)?;
+            return Ok(if let Ok(v) = i8::try_from(v) {
+                NBTTag::Byte(tag::Byte(v))
+            } else if let Ok(v) = i16::try_from(v) {
+                NBTTag::Short(tag::Short(v))
+            } else if let Ok(v) = i32::try_from(v) {
+                NBTTag::Int(tag::Int(v))
+            } else {
+                NBTTag::Long(tag::Long(v))
+            });
+        }
+        _ if is_integer(token) => return Ok(NBTTag::Int(tag::Int(number(token, offset)?))),
+        _ => {}
+    }
+    // Unquoted tokens only contain ASCII characters, so the suffix is always a single byte.
+    let (body, suffix) = token.split_at(token.len() - 1);
+    Ok(match suffix {
+        "b" | "B" if is_integer(body) => NBTTag::Byte(tag::Byte(number(body, offset)?)),
+        "s" | "S" if is_integer(body) => NBTTag::Short(tag::Short(number(body, offset)?)),
+        "l" | "L" if is_integer(body) => NBTTag::Long(tag::Long(number(body, offset)?)),
+        "f" | "F" if is_float(body, true) => NBTTag::Float(tag::Float(number(body, offset)?)),
+        "d" | "D" if is_float(body, true) => NBTTag::Double(tag::Double(number(body, offset)?)),
+        _ if is_float(token, false) => NBTTag::Double(tag::Double(number(token, offset)?)),
+        _ => NBTTag::String(token.into()),
+    })
+}
+
+fn write_tag(out: &mut String, tag: &NBTTag, opts: &SnbtOpts, depth: usize) {
+    match tag {
+        NBTTag::Byte(v) => write!(out, "{}b", v.0).unwrap(),
+        NBTTag::Short(v) => write!(out, "{}s", v.0).unwrap(),
+        NBTTag::Int(v) => write!(out, "{}", v.0).unwrap(),
+        NBTTag::Long(v) => write!(out, "{}L", v.0).unwrap(),
+        NBTTag::Float(v) => write_float(out, format!("{:?}", v.0), 'f', opts.float_format),
+        NBTTag::Double(v) => write_float(out, format!("{:?}", v.0), 'd', opts.float_format),
+        NBTTag::String(v) => write_string(out, &v.to_string_lossy()),
+        NBTTag::Compound(v) => write_compound(out, v, opts, depth),
+        NBTTag::List(v) => write_list(out, v, opts, depth),
+        NBTTag::ByteArray(v) => write_byte_array(out, v),
+        NBTTag::IntArray(v) => write_int_array(out, v),
+        NBTTag::LongArray(v) => write_long_array(out, v),
+        NBTTag::Unknown(v) => {
+            write_array(out, 'B', v.bytes.iter().map(|v| format!("{}b", *v as i8)))
+        }
+    }
+}
+
+fn write_compound(out: &mut String, compound: &tag::Compound, opts: &SnbtOpts, depth: usize) {
+    let mut entries: Vec<_> = compound.iter().collect();
+    if opts.sort_keys {
+        entries.sort_unstable_by_key(|(key, _)| *key);
+    }
+    out.push('{');
+    for (i, (key, value)) in entries.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_newline(out, opts, depth + 1);
+        write_key(out, key);
+        out.push(':');
+        if opts.indent.is_some() {
+            out.push(' ');
+        }
+        write_tag(out, value, opts, depth + 1);
+    }
+    if !entries.is_empty() {
+        write_newline(out, opts, depth);
+    }
+    out.push('}');
+}
+
+fn write_list(out: &mut String, list: &tag::List, opts: &SnbtOpts, depth: usize) {
+    out.push('[');
+    for (i, value) in list.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_newline(out, opts, depth + 1);
+        write_tag(out, value, opts, depth + 1);
+    }
+    if !list.is_empty() {
+        write_newline(out, opts, depth);
+    }
+    out.push(']');
+}
+
+fn write_byte_array(out: &mut String, array: &tag::ByteArray) {
+    write_array(out, 'B', array.iter().map(|v| format!("{v}b")));
+}
+
+fn write_int_array(out: &mut String, array: &tag::IntArray) {
+    write_array(out, 'I', array.iter().map(|v| v.to_string()));
+}
+
+fn write_long_array(out: &mut String, array: &tag::LongArray) {
+    write_array(out, 'L', array.iter().map(|v| format!("{v}L")));
+}
+
+/// Starts a new line indented to the given depth, if [SnbtOpts::indent] is set.
+fn write_newline(out: &mut String, opts: &SnbtOpts, depth: usize) {
+    if let Some(indent) = opts.indent {
+        out.push('\n');
+        out.push_str(&" ".repeat(indent * depth));
+    }
+}
+
+/// Writes a float that was formatted using [Debug], which always produces the shortest
+/// representation that round-trips.
+fn write_float(out: &mut String, mut s: String, suffix: char, format: FloatFormat) {
+    match format {
+        FloatFormat::Explicit => {
+            // Finite values always contain either a decimal point or an exponent.
+            if let (false, Some(i)) = (s.contains('.'), s.find('e')) {
+                s.insert_str(i, ".0");
+            }
+            out.push_str(&s);
+            out.push(suffix);
+        }
+        FloatFormat::Shortest => {
+            if let Some(stripped) = s.strip_suffix(".0") {
+                s.truncate(stripped.len());
+            }
+            out.push_str(&s);
+            if suffix != 'd' || !s.contains('.') {
+                out.push(suffix);
+            }
+        }
+    }
+}
+
+fn write_array(out: &mut String, prefix: char, values: impl Iterator<Item = String>) {
+    out.push('[');
+    out.push(prefix);
+    out.push(';');
+    for (i, value) in values.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&value);
+    }
+    out.push(']');
+}
+
+/// Writes a compound key, which is only quoted if it contains characters that are not allowed in
+/// unquoted keys.
+fn write_key(out: &mut String, key: &str) {
+    let unquoted = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+'));
+    if unquoted {
+        out.push_str(key);
+    } else {
+        write_string(out, key);
+    }
+}
+
+/// Writes a double-quoted string, escaping any backslashes and double quotes.
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        if matches!(c, '"' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::err::SnbtError;
+    use crate::snbt::{FloatFormat, SnbtOpts};
+    use crate::{tag, NBTTag};
+
+    #[test]
+    fn test_to_snbt() {
+        let nbt = nbt!({
+            "byte": 1i8,
+            "list": [1i16, -2i16],
+            "key with spaces": "a \"quoted\" \\ string",
+            "arrays": [[B; 1, -1], [B;]],
+            "ints": [I; 1, 2],
+            "longs": [L; -1],
+        });
+        let opts = SnbtOpts {
+            sort_keys: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            nbt.to_snbt_with(&opts),
+            r#"{arrays:[[B;1b,-1b],[B;]],byte:1b,ints:[I;1,2],"key with spaces":"a \"quoted\" \\ string",list:[1s,-2s],longs:[L;-1L]}"#
+        );
+    }
+
+    #[test]
+    fn test_float_format() {
+        let cases = [
+            (NBTTag::from(1f32), "1.0f", "1f"),
+            (NBTTag::from(-0.5f32), "-0.5f", "-0.5f"),
+            (NBTTag::from(1f64), "1.0d", "1d"),
+            (NBTTag::from(0.1f64), "0.1d", "0.1"),
+            (NBTTag::from(1e30f64), "1.0e30d", "1e30d"),
+            (NBTTag::from(1.5e-7f32), "1.5e-7f", "1.5e-7f"),
+        ];
+        let shortest = SnbtOpts {
+            float_format: FloatFormat::Shortest,
+            ..Default::default()
+        };
+        for (nbt, explicit, short) in cases {
+            assert_eq!(nbt.to_snbt(), explicit);
+            assert_eq!(nbt.to_snbt_with(&shortest), short);
+        }
+        assert_eq!(
+            NBTTag::Compound(tag::Compound::from([("a", 0.25f32)])).to_snbt(),
+            "{a:0.25f}"
+        );
+    }
+
+    #[test]
+    fn test_snbt_not_vanilla() {
+        // Minecraft has no syntax for non-finite values, so these only read back using this crate.
+        let cases = [
+            (NBTTag::from(f64::NAN), "NaNd"),
+            (NBTTag::from(f32::INFINITY), "inff"),
+            (NBTTag::from(f32::NEG_INFINITY), "-inff"),
+        ];
+        for (nbt, snbt) in cases {
+            assert_eq!(nbt.to_snbt(), snbt);
+            assert_eq!(NBTTag::from_snbt(snbt).unwrap().tag_type(), nbt.tag_type());
+        }
+        assert!(matches!(
+            NBTTag::from_snbt("NaNd"),
+            Ok(NBTTag::Double(v)) if v.0.is_nan()
+        ));
+        let nbt = nbt!({ "infinite": [f32::INFINITY, f32::NEG_INFINITY] });
+        assert_eq!(NBTTag::from_snbt(&nbt.to_snbt_pretty()), Ok(nbt));
+
+        // Strings that are not valid UTF-8 are written lossily.
+        let nbt = NBTTag::String(tag::String::Bytes(vec![b'a', 0xff]));
+        assert_eq!(nbt.to_snbt(), "\"a\u{fffd}\"");
+        assert_ne!(NBTTag::from_snbt(&nbt.to_snbt()), Ok(nbt));
+    }
+
+    #[test]
+    fn test_from_snbt() {
+        let nbt = NBTTag::from_snbt(
+            r#" { byte: 1b, short:-2S, int:3, long:4l, float:0.5f, double:1.5, exp:1e3,
+                "quoted key": 'it\'s "quoted"', id: minecraft.stone,
+                flags: [true, false], empty: [], compound: {},
+                arrays: [[B; 1b, -1B], [B;]], ints: [I;1,2], longs: [L; -1L] } "#,
+        );
+        assert_eq!(
+            nbt,
+            Ok(nbt!({
+                "byte": 1i8,
+                "short": -2i16,
+                "int": 3i32,
+                "long": 4i64,
+                "float": 0.5f32,
+                "double": 1.5f64,
+                "exp": 1000f64,
+                "quoted key": "it's \"quoted\"",
+                "id": "minecraft.stone",
+                "flags": [1i8, 0i8],
+                "empty": [],
+                "compound": {},
+                "arrays": [[B; 1, -1], [B;]],
+                "ints": [I; 1, 2],
+                "longs": [L; -1],
+            }))
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(tag::ByteArray(vec![1, 2, 3]).to_string(), "[B;1b,2b,3b]");
+        assert_eq!(tag::IntArray(vec![1, -2]).to_string(), "[I;1,-2]");
+        assert_eq!(tag::LongArray(vec![]).to_string(), "[L;]");
+        assert_eq!(tag::List::from([1i8, 2]).to_string(), "[1b,2b]");
+        assert_eq!(
+            tag::Compound::from([("a", 1i32), ("b c", 2i32)]).to_string(),
+            r#"{a:1,"b c":2}"#
+        );
+        let nbt = nbt!({"list": [[I; 1]]});
+        assert_eq!(nbt.to_string(), "{list:[[I;1]]}");
+        assert_eq!(format!("{nbt:#}"), nbt.to_snbt_pretty());
+    }
+
+    #[test]
+    fn test_to_snbt_pretty() {
+        let nbt = nbt!({
+            "name": "Zuri",
+            "pos": [I; 0, 64, 0],
+            "items": [{"id": 1i16}, {}],
+            "empty": [],
+        });
+        assert_eq!(
+            nbt.to_snbt_pretty(),
+            r#"{
+    name: "Zuri",
+    pos: [I;0,64,0],
+    items: [
+        {
+            id: 1s
+        },
+        {}
+    ],
+    empty: []
+}"#
+        );
+    }
+
+    #[test]
+    fn test_snbt_round_trip() {
+        // Only values that Minecraft can read back as well.
+        let nbt = nbt!({
+            "byte": 1i8,
+            "short": i16::MIN,
+            "int": i32::MAX,
+            "long": i64::MIN,
+            "float": 1e30f32,
+            "double": -0.1f64,
+            "string": "a \"quoted\" \\ string with 'quotes'",
+            "true": "true",
+            "number": "123",
+            "key with spaces": "",
+            "list": [1i16, -2i16],
+            "nested": [[{"a": [L; 1]}], []],
+            "compound": {"": {}},
+            "bytes": [B; 1, -1],
+            "ints": [I; 1, 2],
+            "longs": [L; -1],
+            "empty arrays": [[I;], [I; 1]],
+        });
+        let shortest = SnbtOpts {
+            float_format: FloatFormat::Shortest,
+            ..Default::default()
+        };
+        assert_eq!(NBTTag::from_snbt(&nbt.to_snbt()), Ok(nbt.clone()));
+        assert_eq!(
+            NBTTag::from_snbt(&nbt.to_snbt_with(&shortest)),
+            Ok(nbt.clone())
+        );
+        assert_eq!(NBTTag::from_snbt(&nbt.to_snbt_pretty()), Ok(nbt));
+    }
+
+    #[test]
+    fn test_from_snbt_lenient() {
+        let opts = SnbtOpts {
+            lenient: true,
+            ..Default::default()
+        };
+        let nbt = NBTTag::from_snbt_with(
+            "{byte:1,short:300,int:70000,long:5000000000,double:1.5,suffixed:1s,str:abc}",
+            &opts,
+        )
+        .unwrap();
+        assert_eq!(
+            nbt,
+            nbt!({
+                "byte": 1i8,
+                "short": 300i16,
+                "int": 70000,
+                "long": 5000000000i64,
+                "double": 1.5f64,
+                "suffixed": 1i16,
+                "str": "abc",
+            })
+        );
+
+        assert_eq!(
+            NBTTag::from_snbt_with("[1,-2,3]", &opts),
+            Ok(NBTTag::ByteArray(tag::ByteArray(vec![1, -2, 3])))
+        );
+        assert_eq!(
+            NBTTag::from_snbt_with("[1,300]", &opts),
+            Ok(NBTTag::IntArray(tag::IntArray(vec![1, 300])))
+        );
+        assert_eq!(
+            NBTTag::from_snbt_with("[1,5000000000]", &opts),
+            Ok(NBTTag::LongArray(tag::LongArray(vec![1, 5000000000])))
+        );
+        assert_eq!(
+            NBTTag::from_snbt_with("[L;1,2]", &opts),
+            Ok(NBTTag::LongArray(tag::LongArray(vec![1, 2])))
+        );
+        assert_eq!(
+            NBTTag::from_snbt_with("[1b,2b]", &opts),
+            Ok(nbt!([1i8, 2i8]))
+        );
+        assert_eq!(
+            NBTTag::from_snbt_with("[B;1,300]", &opts),
+            Err(SnbtError::InvalidArrayElement(5))
+        );
+        assert_eq!(
+            NBTTag::from_snbt_with("[1,2.5]", &opts),
+            Err(SnbtError::MixedList(3))
+        );
+
+        // Strict mode reads integers without a suffix as ints, and requires array prefixes.
+        assert_eq!(NBTTag::from_snbt("[1,300]"), Ok(nbt!([1, 300])));
+        assert_eq!(
+            NBTTag::from_snbt("[L;1]"),
+            Err(SnbtError::InvalidArrayElement(3))
+        );
+    }
+
+    #[test]
+    fn test_snbt_errors() {
+        let cases = [
+            ("", SnbtError::UnexpectedEnd(0)),
+            ("{a:1", SnbtError::UnexpectedEnd(4)),
+            (
+                "{a 1}",
+                SnbtError::Expected {
+                    offset: 3,
+                    expected: "`:`",
+                },
+            ),
+            (
+                "{:1}",
+                SnbtError::Expected {
+                    offset: 1,
+                    expected: "a key",
+                },
+            ),
+            ("[1, 2b]", SnbtError::MixedList(4)),
+            ("[I; 1, 2L]", SnbtError::InvalidArrayElement(7)),
+            ("{a: 300b}", SnbtError::InvalidNumber(4)),
+            (r#""a\n""#, SnbtError::InvalidEscape(2)),
+            ("{} x", SnbtError::TrailingInput(3)),
+            (
+                "{id:minecraft:stone}",
+                SnbtError::Expected {
+                    offset: 13,
+                    expected: "`,` or `}`",
+                },
+            ),
+            ("'unterminated", SnbtError::UnexpectedEnd(13)),
+        ];
+        for (input, err) in cases {
+            assert_eq!(NBTTag::from_snbt(input), Err(err.clone()), "{input}");
+            assert_eq!(NBTTag::from_snbt(input).unwrap_err().offset(), err.offset());
+        }
+
+        let deep = "[".repeat(513) + &"]".repeat(513);
+        assert_eq!(
+            NBTTag::from_snbt(&deep),
+            Err(SnbtError::DepthLimitExceeded(512))
+        );
+        let deep = "[".repeat(512) + &"]".repeat(512);
+        assert!(NBTTag::from_snbt(&deep).is_ok());
+    }
+}
@@ -1,5 +1,5 @@
 //! Contains all NBT tags.
-use std::collections::HashMap;
+use indexmap::IndexMap;
 
 use crate::NBTTag;
 
@@ -20,10 +20,18 @@ pub struct Int(pub i32);
 pub struct Long(pub i64);
 
 /// A 32-bit floating point number.
+///
+/// Reading and writing a float preserves its exact bit pattern, including the sign of zero and the
+/// payload of NaN values. Note that [PartialEq] follows the IEEE 754 semantics instead, meaning
+/// that `-0.0 == 0.0` and `NaN != NaN`. Use [Float::bits_eq] to compare the exact bits.
 #[derive(Default, Debug, Copy, Clone, PartialEq)]
 pub struct Float(pub f32);
 
 /// A 64-bit floating point number.
+///
+/// Reading and writing a double preserves its exact bit pattern, including the sign of zero and
+/// the payload of NaN values. Note that [PartialEq] follows the IEEE 754 semantics instead,
+/// meaning that `-0.0 == 0.0` and `NaN != NaN`. Use [Double::bits_eq] to compare the exact bits.
 #[derive(Default, Debug, Copy, Clone, PartialEq)]
 pub struct Double(pub f64);
 
@@ -40,16 +48,25 @@ pub enum String {
 
 /// A map containing zero or more key-value pairs.
 ///
-/// Each key maps to exactly one [NBTTag] of any type.
+/// Each key maps to exactly one [NBTTag] of any type. Entries are kept in the order in which they
+/// were inserted, which is also the order in which they are read and written.
 #[derive(Default, Debug, Clone, PartialEq)]
-pub struct Compound(pub HashMap<std::string::String, NBTTag>);
+pub struct Compound(pub IndexMap<std::string::String, NBTTag>);
 
 /// A variable-length list [NBTTag]s of the same type.
 ///
 /// Lists will fail to encode/decode should it contain values of which the type does not match
 /// the type of the first element in the list.
-#[derive(Default, Debug, Clone, PartialEq)]
-pub struct List(pub Vec<NBTTag>);
+///
+/// The content type that an empty list was read with is kept, so that it is written back the same
+/// way. See [List::declared_type]. Two lists are equal if their elements are equal, regardless of
+/// their content type.
+#[derive(Default, Debug, Clone)]
+pub struct List(
+    pub Vec<NBTTag>,
+    /// The id of the content type of an empty list that was read, if any.
+    pub(crate) Option<u8>,
+);
 
 /// A variable-length array containing 8-bit signed integers.
 #[derive(Default, Debug, Clone, Eq, PartialEq)]
@@ -63,9 +80,60 @@ pub struct IntArray(pub Vec<i32>);
 #[derive(Default, Debug, Clone, Eq, PartialEq)]
 pub struct LongArray(pub Vec<i64>);
 
+/// A tag with an id that is not known to this crate, of which the payload is kept as raw bytes so
+/// that it can be written again without losing any data.
+///
+/// Unknown tags are only read if their id is present in [crate::decode::ReadOpts::unknown_arrays].
+/// The bytes are kept exactly as they were read, so writing the tag using a different encoding
+/// than the one it was read with does not convert the byte order of its elements.
+#[derive(Default, Debug, Clone, Eq, PartialEq)]
+pub struct Unknown {
+    /// The id of the tag.
+    pub id: u8,
+    /// The number of elements in the array, which is written as the length prefix of the payload.
+    pub len: i32,
+    /// The payload following the length prefix.
+    pub bytes: Vec<u8>,
+}
+
+/// Contains the ids that identify the type of a tag in encoded NBT data.
+pub mod ids {
+    /// The id of the end tag, which marks the end of a [super::Compound].
+    pub const END: u8 = 0;
+    /// The id of a [super::Byte] tag.
+    pub const BYTE: u8 = 1;
+    /// The id of a [super::Short] tag.
+    pub const SHORT: u8 = 2;
+    /// The id of an [super::Int] tag.
+    pub const INT: u8 = 3;
+    /// The id of a [super::Long] tag.
+    pub const LONG: u8 = 4;
+    /// The id of a [super::Float] tag.
+    pub const FLOAT: u8 = 5;
+    /// The id of a [super::Double] tag.
+    pub const DOUBLE: u8 = 6;
+    /// The id of a [super::ByteArray] tag.
+    pub const BYTE_ARRAY: u8 = 7;
+    /// The id of a [super::String] tag.
+    pub const STRING: u8 = 8;
+    /// The id of a [super::List] tag.
+    pub const LIST: u8 = 9;
+    /// The id of a [super::Compound] tag.
+    pub const COMPOUND: u8 = 10;
+    /// The id of an [super::IntArray] tag.
+    pub const INT_ARRAY: u8 = 11;
+    /// The id of a [super::LongArray] tag.
+    pub const LONG_ARRAY: u8 = 12;
+}
+
 /// Contains utilities for the [Compound] NBT tag.
 pub mod compound {
-    use crate::{tag, NBTTag};
+    use crate::err::{Path, PathError, PathPart};
+    use crate::{tag, NBTTag, NBTTagType};
+    use indexmap::map::Drain;
+    use indexmap::Equivalent;
+    use std::hash::Hash;
+    use std::ops::RangeBounds;
 
     /// Allows for a more ergonomic way of creating NBT compound tags.
     #[must_use]
@@ -81,6 +149,457 @@ pub mod compound {
                 value: Default::default(),
             }
         }
+
+        /// Returns a builder object that starts out with the entries of this compound, so that more
+        /// entries can be added to it.
+        ///
+        /// As with any builder, adding a key that already exists in the compound panics.
+        pub fn into_builder(self) -> Builder {
+            Builder { value: self }
+        }
+
+        /// Returns the total number of tags nested in the compound, including the entries of nested
+        /// compound tags and the elements of nested list tags at any depth.
+        ///
+        /// The elements of array tags are not tags themselves, so an array tag only counts as one.
+        pub fn entry_count_deep(&self) -> usize {
+            let mut count = 0;
+            let mut stack: Vec<&NBTTag> = self.values().collect();
+            while let Some(tag) = stack.pop() {
+                count += 1;
+                match tag {
+                    NBTTag::Compound(v) => stack.extend(v.values()),
+                    NBTTag::List(v) => stack.extend(v.iter()),
+                    _ => {}
+                }
+            }
+            count
+        }
+
+        /// Iterates over the direct children of the compound that are of the given type, along
+        /// with their keys, in the order of the compound. Nested tags are not visited.
+        pub fn values_of_type(
+            &self,
+            tag_type: NBTTagType,
+        ) -> impl Iterator<Item = (&str, &NBTTag)> + '_ {
+            self.0
+                .iter()
+                .filter(move |(_, v)| v.tag_type() == tag_type)
+                .map(|(k, v)| (k.as_str(), v))
+        }
+
+        /// Removes all entries of which the key is not one of the provided keys, for example to
+        /// redact all but a few top-level keys of player data before sharing it.
+        ///
+        /// The entries that are kept are not otherwise modified or reordered.
+        pub fn keep_only(&mut self, keys: &[&str]) {
+            self.0.retain(|key, _| keys.contains(&key.as_str()));
+        }
+
+        /// Removes all entries for which `f` returns true, given the key and value of the entry.
+        /// The entries that are kept are not otherwise modified or reordered.
+        ///
+        /// Combined with a closure that compares entries against their default values, this can
+        /// be used to strip defaults from data before writing it:
+        /// ```
+        /// # use zuri_nbt::{tag, NBTTag};
+        /// let defaults = tag::Compound::builder()
+        ///     .with_byte("Invulnerable", 0)
+        ///     .with_short("Fire", -1)
+        ///     .build();
+        /// let mut entity = tag::Compound::builder()
+        ///     .with_byte("Invulnerable", 0)
+        ///     .with_short("Fire", 20)
+        ///     .with_string("id", "minecraft:pig")
+        ///     .build();
+        /// entity.remove_matching(|key, value| defaults.get(key) == Some(value));
+        /// assert_eq!(entity.keys().collect::<Vec<_>>(), ["Fire", "id"]);
+        /// ```
+        pub fn remove_matching(&mut self, f: impl Fn(&str, &NBTTag) -> bool) {
+            self.0.retain(|key, value| !f(key, value));
+        }
+
+        /// Renames the keys of all entries using `f`, which returns the new key for a key, or
+        /// `None` to keep the key unchanged. The order of the entries is preserved.
+        ///
+        /// If multiple entries end up with the same key, the last of them in the order of the
+        /// compound wins: its value is kept at the position of the first of them, and the values
+        /// of the others are dropped.
+        pub fn rename_keys_with(&mut self, f: impl Fn(&str) -> Option<String>) {
+            let entries = std::mem::take(&mut self.0);
+            self.0.reserve(entries.len());
+            for (key, value) in entries {
+                let key = f(&key).unwrap_or(key);
+                self.0.insert(key, value);
+            }
+        }
+
+        /// Removes the entries in the range of positions from the compound, returning them as an
+        /// iterator of owned key-value pairs. This allows the entries to be transformed and moved
+        /// into another compound without cloning. Use `drain(..)` to remove all entries.
+        ///
+        /// The entries are removed after the iterator is dropped, even if it was not fully
+        /// consumed.
+        ///
+        /// # Panics
+        ///
+        /// Panics if the start of the range is greater than its end, or if its end is greater
+        /// than the number of entries.
+        pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, String, NBTTag> {
+            self.0.drain(range)
+        }
+
+        /// Returns a reference to the value stored under the given key, if present.
+        ///
+        /// The key is looked up by reference, so no [String] is allocated for the lookup.
+        pub fn get<Q>(&self, key: &Q) -> Option<&NBTTag>
+        where
+            Q: ?Sized + Hash + Equivalent<String>,
+        {
+            self.0.get(key)
+        }
+
+        /// Returns a mutable reference to the value stored under the given key, if present.
+        pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut NBTTag>
+        where
+            Q: ?Sized + Hash + Equivalent<String>,
+        {
+            self.0.get_mut(key)
+        }
+
+        /// Returns true if the compound contains an entry with the given key.
+        pub fn contains_key<Q>(&self, key: &Q) -> bool
+        where
+            Q: ?Sized + Hash + Equivalent<String>,
+        {
+            self.0.contains_key(key)
+        }
+
+        /// Returns the position of the entry with the given key, which is the number of entries
+        /// that were inserted before it.
+        pub fn index_of(&self, key: &str) -> Option<usize> {
+            self.0.get_index_of(key)
+        }
+
+        /// Returns the key and value of the entry at the given position, if present.
+        pub fn get_index(&self, i: usize) -> Option<(&String, &NBTTag)> {
+            self.0.get_index(i)
+        }
+
+        /// Swaps the positions of the entries at positions `a` and `b`, changing the order in
+        /// which they are written.
+        ///
+        /// # Panics
+        /// Panics if either position is out of bounds.
+        pub fn swap_indices(&mut self, a: usize, b: usize) {
+            self.0.swap_indices(a, b);
+        }
+
+        /// Moves the entry at position `from` to position `to`, shifting all entries in between
+        /// by one position.
+        ///
+        /// # Panics
+        /// Panics if either position is out of bounds.
+        pub fn move_index(&mut self, from: usize, to: usize) {
+            self.0.move_index(from, to);
+        }
+    }
+
+    impl super::Compound {
+        /// Gets a nested NBT tag by following a [Path] starting at this compound.
+        ///
+        /// [PathPart::MapKey] and [PathPart::Field] descend into compound tags,
+        /// [PathPart::TupleField] descends into the compound key of the same number, and
+        /// [PathPart::Element] indexes into list tags. Elements of array tags cannot be referenced
+        /// as they are not stored as NBT tags. Use a [crate::view::View] to read those instead.
+        ///
+        /// Returns `None` if the path is empty, a part of the path is missing, or a part of the
+        /// path does not match the type of the tag it is applied to.
+        pub fn get_path(&self, path: &[PathPart]) -> Option<&NBTTag> {
+            let (first, rest) = path.split_first()?;
+            let mut tag = self.get_part(first)?;
+            for part in rest {
+                tag = match (tag, part) {
+                    (NBTTag::Compound(v), part) => v.get_part(part)?,
+                    (NBTTag::List(v), PathPart::Element(i)) => v.get(*i)?,
+                    _ => return None,
+                };
+            }
+            Some(tag)
+        }
+
+        /// Gets a nested NBT tag by following a textual path such as `Inventory[0].tag.Name`,
+        /// starting at this compound.
+        ///
+        /// Keys that contain a `.` or `[` can be escaped using a backslash, or written in bracket
+        /// notation such as `["mod.data"].level`. See [Path] for the full syntax. Returns `None` if
+        /// the path could not be parsed or if no tag exists at the path.
+        pub fn get_str_path(&self, path: &str) -> Option<&NBTTag> {
+            let path: Path = path.parse().ok()?;
+            self.get_path(Vec::from(path.0).as_slice())
+        }
+
+        /// Gets a nested NBT tag by following pre-split path segments, starting at this compound.
+        ///
+        /// Each segment is used as a key when applied to a compound tag, and parsed as an index
+        /// when applied to a list tag. Since the segments never need to be split or unescaped, this
+        /// works for any key, including keys that contain `.`, `[` or `\`.
+        pub fn get_path_segments(&self, segments: &[&str]) -> Option<&NBTTag> {
+            let (first, rest) = segments.split_first()?;
+            let mut tag = self.get(*first)?;
+            for segment in rest {
+                tag = match tag {
+                    NBTTag::Compound(v) => v.get(*segment)?,
+                    NBTTag::List(v) => v.get(segment.parse::<usize>().ok()?)?,
+                    _ => return None,
+                };
+            }
+            Some(tag)
+        }
+
+        /// Gets an integer by following a textual path such as `Data.Time`, starting at this
+        /// compound, or returns the default if no integer tag exists at the path.
+        ///
+        /// Byte, short, int and long tags are all accepted and converted to an i64, in the same way
+        /// as [crate::view::View::any_int]. See [Path] for the path syntax.
+        pub fn get_path_i64_or(&self, path: &str, default: i64) -> i64 {
+            self.get_str_path(path)
+                .and_then(|tag| tag.view().any_int().ok())
+                .unwrap_or(default)
+        }
+
+        /// Gets a floating point number by following a textual path such as `Data.BorderSize`,
+        /// starting at this compound, or returns the default if no float or double tag exists at
+        /// the path.
+        ///
+        /// Float tags are converted to an f64, in the same way as
+        /// [crate::view::View::any_float]. See [Path] for the path syntax.
+        pub fn get_path_f64_or(&self, path: &str, default: f64) -> f64 {
+            self.get_str_path(path)
+                .and_then(|tag| tag.view().any_float().ok())
+                .unwrap_or(default)
+        }
+
+        /// Gets a string by following a textual path such as `Data.LevelName`, starting at this
+        /// compound, or returns the default if no string tag exists at the path.
+        ///
+        /// String tags that are not valid UTF-8 are treated as missing. See [Path] for the path
+        /// syntax.
+        pub fn get_path_str_or<'a>(&'a self, path: &str, default: &'a str) -> &'a str {
+            self.get_str_path(path)
+                .and_then(|tag| tag.view().string().ok())
+                .unwrap_or(default)
+        }
+
+        /// Gets the values of a byte array stored at the key, which may be stored either as a byte
+        /// array tag or as a list of byte tags. Returns `None` if the key is missing or holds any
+        /// other tag.
+        pub fn get_i8_array(&self, key: &str) -> Option<Vec<i8>> {
+            match self.get(key)? {
+                NBTTag::ByteArray(v) => Some(v.0.clone()),
+                NBTTag::List(v) => v.as_i8_vec(),
+                _ => None,
+            }
+        }
+
+        /// Gets the values of an int array stored at the key, which may be stored either as an int
+        /// array tag or as a list of int tags. Returns `None` if the key is missing or holds any
+        /// other tag.
+        pub fn get_i32_array(&self, key: &str) -> Option<Vec<i32>> {
+            match self.get(key)? {
+                NBTTag::IntArray(v) => Some(v.0.clone()),
+                NBTTag::List(v) => v.as_i32_vec(),
+                _ => None,
+            }
+        }
+
+        /// Gets the values of a long array stored at the key, which may be stored either as a long
+        /// array tag or as a list of long tags. Returns `None` if the key is missing or holds any
+        /// other tag.
+        pub fn get_i64_array(&self, key: &str) -> Option<Vec<i64>> {
+            match self.get(key)? {
+                NBTTag::LongArray(v) => Some(v.0.clone()),
+                NBTTag::List(v) => v.as_i64_vec(),
+                _ => None,
+            }
+        }
+
+        /// Removes a nested NBT tag by following a textual path such as `display.Lore[0]`,
+        /// starting at this compound, and returns the removed tag.
+        ///
+        /// Removing an element from a list tag shifts all elements after it to the left. If the
+        /// path could not be parsed or no tag exists at the path, `None` is returned and the
+        /// compound is left untouched. See [Path] for the path syntax.
+        pub fn remove_path(&mut self, path: &str) -> Option<NBTTag> {
+            let path: Path = path.parse().ok()?;
+            let path = Vec::from(path.0);
+            let (last, parents) = path.split_last()?;
+            if parents.is_empty() {
+                return self.remove_part(last);
+            }
+            match self.get_path_mut(parents)? {
+                NBTTag::Compound(v) => v.remove_part(last),
+                NBTTag::List(v) => match last {
+                    PathPart::Element(i) if *i < v.len() => Some(v.remove(*i)),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+
+        /// Sets a nested NBT tag by following a textual path such as `display.Lore[0]`, starting
+        /// at this compound, and returns the tag that was previously present at the path.
+        ///
+        /// If `create_missing` is true, empty compound tags are inserted for any missing
+        /// intermediate keys. Otherwise, a missing intermediate tag results in a
+        /// [PathError::MissingTag] error. Setting a list index that is out of bounds never extends
+        /// the list, and results in a [PathError::IndexOutOfBounds] error instead. If the path is
+        /// invalid, the compound is left untouched, apart from any intermediate compound tags that
+        /// were created before the error was found.
+        ///
+        /// Note that this does not check whether the type of the value matches the other elements
+        /// when setting an element of a list.
+        pub fn set_path(
+            &mut self,
+            path: &str,
+            value: NBTTag,
+            create_missing: bool,
+        ) -> Result<Option<NBTTag>, PathError> {
+            let path: Path = path.parse()?;
+            let path = Vec::from(path.0);
+            let (last, parents) = path.split_last().ok_or(PathError::InvalidSyntax(0))?;
+            let Some((first, rest)) = parents.split_first() else {
+                return self.set_part(last, 0, value);
+            };
+
+            let mut tag = self.child_part_mut(first, 0, create_missing)?;
+            for (i, part) in rest.iter().enumerate() {
+                tag = match (tag, part) {
+                    (NBTTag::Compound(v), part) => v.child_part_mut(part, i + 1, create_missing)?,
+                    (NBTTag::List(v), PathPart::Element(index)) => {
+                        let len = v.len();
+                        v.get_mut(*index).ok_or(PathError::IndexOutOfBounds {
+                            part: i + 1,
+                            index: *index,
+                            len,
+                        })?
+                    }
+                    (tag, _) => {
+                        return Err(PathError::TypeMismatch {
+                            part: i + 1,
+                            found: tag.tag_type(),
+                        })
+                    }
+                };
+            }
+
+            let part = parents.len();
+            match (tag, last) {
+                (NBTTag::Compound(v), last) => v.set_part(last, part, value),
+                (NBTTag::List(v), PathPart::Element(index)) => {
+                    let len = v.len();
+                    match v.get_mut(*index) {
+                        Some(old) => Ok(Some(std::mem::replace(old, value))),
+                        None => Err(PathError::IndexOutOfBounds {
+                            part,
+                            index: *index,
+                            len,
+                        }),
+                    }
+                }
+                (tag, _) => Err(PathError::TypeMismatch {
+                    part,
+                    found: tag.tag_type(),
+                }),
+            }
+        }
+
+        /// Gets a nested NBT tag mutably by following a [Path] starting at this compound.
+        ///
+        /// See [Self::get_path] for more info.
+        pub fn get_path_mut(&mut self, path: &[PathPart]) -> Option<&mut NBTTag> {
+            let (first, rest) = path.split_first()?;
+            let mut tag = self.get_part_mut(first)?;
+            for part in rest {
+                tag = match (tag, part) {
+                    (NBTTag::Compound(v), part) => v.get_part_mut(part)?,
+                    (NBTTag::List(v), PathPart::Element(i)) => v.get_mut(*i)?,
+                    _ => return None,
+                };
+            }
+            Some(tag)
+        }
+
+        /// Gets a direct child of the compound using a single [PathPart].
+        pub(crate) fn get_part(&self, part: &PathPart) -> Option<&NBTTag> {
+            match part {
+                PathPart::MapKey(k) | PathPart::Field(k) => self.get(k),
+                PathPart::TupleField(i) => self.get(&i.to_string()),
+                PathPart::Element(_) => None,
+            }
+        }
+
+        /// Gets a direct child of the compound mutably using a single [PathPart].
+        pub(crate) fn get_part_mut(&mut self, part: &PathPart) -> Option<&mut NBTTag> {
+            match part {
+                PathPart::MapKey(k) | PathPart::Field(k) => self.get_mut(k),
+                PathPart::TupleField(i) => self.get_mut(&i.to_string()),
+                PathPart::Element(_) => None,
+            }
+        }
+
+        /// Gets a direct child of the compound mutably using the [PathPart] at position `i` of a
+        /// path, inserting an empty compound tag if it is missing and `create_missing` is true.
+        fn child_part_mut(
+            &mut self,
+            part: &PathPart,
+            i: usize,
+            create_missing: bool,
+        ) -> Result<&mut NBTTag, PathError> {
+            let key = match part {
+                PathPart::MapKey(k) | PathPart::Field(k) => k.clone(),
+                PathPart::TupleField(t) => t.to_string(),
+                PathPart::Element(_) => {
+                    return Err(PathError::TypeMismatch {
+                        part: i,
+                        found: NBTTagType::Compound,
+                    })
+                }
+            };
+            if create_missing {
+                Ok(self
+                    .entry(key)
+                    .or_insert_with(|| NBTTag::Compound(Default::default())))
+            } else {
+                self.get_mut(&key).ok_or(PathError::MissingTag(i))
+            }
+        }
+
+        /// Sets a direct child of the compound using the [PathPart] at position `i` of a path.
+        pub(crate) fn set_part(
+            &mut self,
+            part: &PathPart,
+            i: usize,
+            value: NBTTag,
+        ) -> Result<Option<NBTTag>, PathError> {
+            match part {
+                PathPart::MapKey(k) | PathPart::Field(k) => Ok(self.insert(k.clone(), value)),
+                PathPart::TupleField(t) => Ok(self.insert(t.to_string(), value)),
+                PathPart::Element(_) => Err(PathError::TypeMismatch {
+                    part: i,
+                    found: NBTTagType::Compound,
+                }),
+            }
+        }
+
+        /// Removes a direct child of the compound using a single [PathPart].
+        pub(crate) fn remove_part(&mut self, part: &PathPart) -> Option<NBTTag> {
+            match part {
+                PathPart::MapKey(k) | PathPart::Field(k) => self.shift_remove(k),
+                PathPart::TupleField(i) => self.shift_remove(&i.to_string()),
+                PathPart::Element(_) => None,
+            }
+        }
     }
 
     impl Builder {
@@ -193,3 +712,328 @@ pub mod compound {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::err::{PathError, PathPart};
+    use crate::{tag, NBTTag, NBTTagType};
+
+    fn sample() -> tag::Compound {
+        tag::Compound::builder()
+            .with_compound(
+                "display",
+                tag::Compound::builder()
+                    .with_string("Name", "Sword")
+                    .with_list("Lore", vec![tag::String::from("a"), tag::String::from("b")]),
+            )
+            .with_compound("mod.data", tag::Compound::builder().with_int("level", 3))
+            .build()
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut compound = tag::Compound::from([("a", 1i32), ("b", 2i32)]);
+        let renamed: tag::Compound = compound
+            .drain(..)
+            .map(|(k, v)| (k.to_uppercase(), v))
+            .collect::<std::collections::HashMap<_, _>>()
+            .into();
+        assert!(compound.is_empty());
+        assert_eq!(renamed, tag::Compound::from([("A", 1i32), ("B", 2i32)]));
+
+        let mut compound = tag::Compound::from([("a", 1i32), ("b", 2i32), ("c", 3i32)]);
+        assert!(compound.drain(1..2).map(|(k, _)| k).eq(["b"]));
+        assert!(compound.keys().eq(["a", "c"]));
+        assert_eq!(compound.get(&"c".to_string()), Some(&NBTTag::from(3i32)));
+    }
+
+    #[test]
+    fn test_index_of() {
+        let mut compound = tag::Compound::from([("b", 1i32), ("a", 2i32), ("c", 3i32)]);
+        assert_eq!(compound.index_of("a"), Some(1));
+        assert_eq!(compound.index_of("d"), None);
+        assert_eq!(
+            compound.get_index(2),
+            Some((&"c".to_string(), &NBTTag::Int(tag::Int(3))))
+        );
+        assert_eq!(compound.get_index(3), None);
+
+        compound.remove_path("a");
+        assert_eq!(compound.index_of("c"), Some(1));
+    }
+
+    #[test]
+    fn test_borrowed_lookup() {
+        let mut compound = tag::Compound::from([("a", 1i32)]);
+        let key = "a".to_string();
+        assert_eq!(compound.get("a"), Some(&NBTTag::Int(tag::Int(1))));
+        assert_eq!(compound.get(&key), compound.get("a"));
+        assert!(compound.contains_key(&key));
+        assert!(!compound.contains_key("b"));
+
+        *compound.get_mut("a").unwrap() = NBTTag::Int(tag::Int(2));
+        assert_eq!(compound.get("a"), Some(&NBTTag::Int(tag::Int(2))));
+        assert_eq!(compound.get_mut("b"), None);
+    }
+
+    #[test]
+    fn test_values_of_type() {
+        let compound = tag::Compound::builder()
+            .with_compound("a", tag::Compound::builder().with_int("x", 1).build())
+            .with_int("b", 2)
+            .with_compound("c", tag::Compound::default())
+            .build();
+        let keys: Vec<_> = compound
+            .values_of_type(NBTTagType::Compound)
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(keys, ["a", "c"]);
+        assert_eq!(compound.values_of_type(NBTTagType::Long).count(), 0);
+    }
+
+    #[test]
+    fn test_rename_keys_with() {
+        let mut compound = tag::Compound::from([("a", 1i32), ("Old", 2i32), ("b", 3i32)]);
+        compound.rename_keys_with(|k| match k {
+            "Old" => Some("new".to_string()),
+            "b" => Some("a".to_string()),
+            _ => None,
+        });
+        assert_eq!(compound.keys().collect::<Vec<_>>(), ["a", "new"]);
+        assert_eq!(compound.get("a"), Some(&NBTTag::Int(tag::Int(3))));
+        assert_eq!(compound.get("new"), Some(&NBTTag::Int(tag::Int(2))));
+    }
+
+    #[test]
+    fn test_get_array() {
+        let compound = tag::Compound::builder()
+            .with_int_array("array", vec![1, 2])
+            .with_list("list", vec![NBTTag::from(3i32), NBTTag::from(4i32)])
+            .with_list("empty", Vec::<NBTTag>::new())
+            .with_list("longs", vec![NBTTag::from(5i64)])
+            .with_int("int", 6)
+            .build();
+        assert_eq!(compound.get_i32_array("array"), Some(vec![1, 2]));
+        assert_eq!(compound.get_i32_array("list"), Some(vec![3, 4]));
+        assert_eq!(compound.get_i32_array("empty"), Some(vec![]));
+        assert_eq!(compound.get_i32_array("longs"), None);
+        assert_eq!(compound.get_i32_array("int"), None);
+        assert_eq!(compound.get_i32_array("missing"), None);
+        assert_eq!(compound.get_i64_array("longs"), Some(vec![5]));
+        assert_eq!(compound.get_i8_array("array"), None);
+    }
+
+    #[test]
+    fn test_reorder() {
+        let mut compound = tag::Compound::from([("a", 1i32), ("b", 2i32), ("c", 3i32)]);
+        compound.swap_indices(0, 2);
+        assert_eq!(compound.keys().collect::<Vec<_>>(), ["c", "b", "a"]);
+        compound.move_index(0, 2);
+        assert_eq!(compound.keys().collect::<Vec<_>>(), ["b", "a", "c"]);
+        assert_eq!(compound.index_of("c"), Some(2));
+    }
+
+    #[test]
+    fn test_into_builder() {
+        let compound = tag::Compound::from([("a", 1i32)])
+            .into_builder()
+            .with_int("added", 2)
+            .build();
+        assert_eq!(
+            compound,
+            tag::Compound::from([("a", 1i32), ("added", 2i32)])
+        );
+    }
+
+    #[test]
+    fn test_keep_only() {
+        let mut compound = tag::Compound::builder()
+            .with_compound("abilities", tag::Compound::builder().with_byte("flying", 1))
+            .with_int("playerGameType", 1)
+            .with_string("LastKnownName", "Zuri")
+            .with_list("Pos", vec![0f64, 64f64, 0f64])
+            .build();
+        compound.keep_only(&["abilities", "playerGameType", "missing"]);
+        let mut keys: Vec<_> = compound.keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["abilities", "playerGameType"]);
+        assert_eq!(compound.entry_count_deep(), 3);
+
+        compound.keep_only(&[]);
+        assert!(compound.is_empty());
+    }
+
+    #[test]
+    fn test_get_path_or() {
+        let nbt = nbt!({
+            "Data": {
+                "Time": 1200i32,
+                "Version": 3i8,
+                "BorderSize": 0.5f32,
+                "LevelName": "world",
+            },
+        });
+        let NBTTag::Compound(nbt) = nbt else {
+            unreachable!()
+        };
+        assert_eq!(nbt.get_path_i64_or("Data.Time", 0), 1200);
+        assert_eq!(nbt.get_path_i64_or("Data.Version", 0), 3);
+        assert_eq!(nbt.get_path_i64_or("Data.Missing", 7), 7);
+        assert_eq!(nbt.get_path_i64_or("Data.LevelName", 7), 7);
+        assert_eq!(nbt.get_path_f64_or("Data.BorderSize", 1.0), 0.5);
+        assert_eq!(nbt.get_path_f64_or("Data.Time", 1.0), 1.0);
+        assert_eq!(nbt.get_path_str_or("Data.LevelName", "a"), "world");
+        assert_eq!(nbt.get_path_str_or("Data[", "a"), "a");
+    }
+
+    #[test]
+    fn test_set_path() {
+        let mut compound = sample();
+        let old = compound.set_path("display.Lore[1]", NBTTag::from("c"), false);
+        assert_eq!(old, Ok(Some(NBTTag::from("b"))));
+        assert_eq!(
+            compound.get_str_path("display.Lore[1]"),
+            Some(&NBTTag::from("c"))
+        );
+        assert_eq!(
+            compound.set_path("added", NBTTag::from(1i8), false),
+            Ok(None)
+        );
+        assert_eq!(compound.get_str_path("added"), Some(&NBTTag::from(1i8)));
+
+        assert_eq!(
+            compound.set_path("a.b.c", NBTTag::from(1i8), false),
+            Err(PathError::MissingTag(0))
+        );
+        assert_eq!(
+            compound.set_path("a.b.c", NBTTag::from(1i8), true),
+            Ok(None)
+        );
+        assert_eq!(compound.get_str_path("a.b.c"), Some(&NBTTag::from(1i8)));
+
+        assert_eq!(
+            compound.set_path("display.Lore[2]", NBTTag::from("d"), true),
+            Err(PathError::IndexOutOfBounds {
+                part: 2,
+                index: 2,
+                len: 2
+            })
+        );
+        assert_eq!(
+            compound.set_path("added.x", NBTTag::from(1i8), true),
+            Err(PathError::TypeMismatch {
+                part: 1,
+                found: NBTTagType::Byte
+            })
+        );
+        assert_eq!(
+            compound.set_path("display[0]", NBTTag::from(1i8), true),
+            Err(PathError::TypeMismatch {
+                part: 1,
+                found: NBTTagType::Compound
+            })
+        );
+        assert_eq!(
+            compound.set_path("", NBTTag::from(1i8), true),
+            Err(PathError::InvalidSyntax(0))
+        );
+    }
+
+    #[test]
+    fn test_remove_path() {
+        let mut nbt = sample();
+        assert_eq!(
+            nbt.remove_path("display.Lore[0]"),
+            Some(NBTTag::String("a".into()))
+        );
+        assert_eq!(
+            nbt.get_str_path("display.Lore[0]"),
+            Some(&NBTTag::String("b".into()))
+        );
+        assert_eq!(nbt.remove_path("display.Lore[1]"), None);
+        assert_eq!(nbt.remove_path("display.Missing"), None);
+        assert_eq!(nbt.remove_path("display.Name.x"), None);
+
+        assert_eq!(
+            nbt.remove_path(r"mod\.data"),
+            Some(NBTTag::Compound(tag::Compound::from([("level", 3i32)])))
+        );
+        assert_eq!(nbt.get_str_path(r"mod\.data"), None);
+    }
+
+    #[test]
+    fn test_get_path() {
+        let nbt = sample();
+        assert_eq!(
+            nbt.get_str_path("display.Lore[1]"),
+            Some(&NBTTag::String("b".into()))
+        );
+        assert_eq!(
+            nbt.get_str_path(r"mod\.data.level"),
+            Some(&NBTTag::Int(3.into()))
+        );
+        assert_eq!(
+            nbt.get_str_path(r#"["mod.data"].level"#),
+            Some(&NBTTag::Int(3.into()))
+        );
+        assert_eq!(nbt.get_str_path("mod.data.level"), None);
+        assert_eq!(nbt.get_str_path("display.Lore[2]"), None);
+        assert_eq!(nbt.get_str_path("display.Name[0]"), None);
+        assert_eq!(nbt.get_str_path("display.Lore[x]"), None);
+
+        assert_eq!(
+            nbt.get_path_segments(&["mod.data", "level"]),
+            Some(&NBTTag::Int(3.into()))
+        );
+        assert_eq!(
+            nbt.get_path_segments(&["display", "Lore", "0"]),
+            Some(&NBTTag::String("a".into()))
+        );
+
+        let escaped = tag::Compound::builder()
+            .with_int("a[0]", 1)
+            .with_int(r"back\slash", 2)
+            .build();
+        assert_eq!(escaped.get_str_path(r"a\[0]"), Some(&NBTTag::Int(1.into())));
+        assert_eq!(escaped.get_str_path("a[0]"), None);
+        assert_eq!(
+            escaped.get_str_path(r"back\\slash"),
+            Some(&NBTTag::Int(2.into()))
+        );
+    }
+
+    #[test]
+    fn test_compound_get_path_mut() {
+        let mut nbt = sample();
+        let path = [
+            PathPart::MapKey("display".into()),
+            PathPart::MapKey("Lore".into()),
+        ];
+        if let Some(NBTTag::List(lore)) = nbt.get_path_mut(&path) {
+            lore.push(NBTTag::String("c".into()));
+        }
+        assert_eq!(
+            nbt.get_str_path("display.Lore[2]"),
+            Some(&NBTTag::String("c".into()))
+        );
+
+        *nbt.get_path_mut(&[
+            PathPart::MapKey("display".into()),
+            PathPart::MapKey("Lore".into()),
+            PathPart::Element(0),
+        ])
+        .unwrap() = NBTTag::String("z".into());
+        assert_eq!(
+            nbt.get_str_path("display.Lore[0]"),
+            Some(&NBTTag::String("z".into()))
+        );
+
+        assert!(nbt.get_path_mut(&[]).is_none());
+        assert!(nbt
+            .get_path_mut(&[PathPart::MapKey("display".into()), PathPart::Element(0)])
+            .is_none());
+        assert!(nbt
+            .get_path_mut(&[PathPart::MapKey("missing".into())])
+            .is_none());
+    }
+}
@@ -0,0 +1,192 @@
+//! See [ValidateOpts].
+use std::io::Write;
+
+use crate::encode::{modified_utf8_len, Writer};
+use crate::err::{ErrorPath, PathPart, ValidateError, WriteError};
+use crate::{encode, tag, NBTTag};
+
+/// Options that determine which checks are performed by [NBTTag::validate].
+///
+/// Lists with mixed element types and strings that are too long to be written are always rejected,
+/// as they can never be written successfully.
+#[derive(Debug, Default, Clone)]
+pub struct ValidateOpts {
+    /// Whether compound keys that are empty strings should be rejected.
+    ///
+    /// Empty keys are valid NBT, but cannot be addressed using textual paths and are often a sign
+    /// of corrupt or adversarial data.
+    pub reject_empty_keys: bool,
+    /// Whether float and double tags that are NaN or infinite should be rejected.
+    ///
+    /// Such values can be written, but are not valid in many other formats such as JSON.
+    pub reject_non_finite: bool,
+}
+
+/// A short notation for the result type used by [NBTTag::validate].
+pub type Res = Result<(), ErrorPath<ValidateError>>;
+
+impl NBTTag {
+    /// Checks the NBT tag and all of its children using the provided options, returning the path
+    /// of the first tag that does not pass.
+    ///
+    /// Note that compound keys that are not valid UTF-8 can never be present in an [NBTTag], as
+    /// they are already rejected with a [crate::err::ReadError::InvalidString] error while reading.
+    pub fn validate(&self, opts: &ValidateOpts) -> Res {
+        match self {
+            NBTTag::Compound(v) => {
+                for (key, value) in v.iter() {
+                    if opts.reject_empty_keys && key.is_empty() {
+                        return Err(ErrorPath::new(ValidateError::EmptyKey)
+                            .prepend(PathPart::MapKey(key.clone())));
+                    }
+                    check_string_len(key.chars().map(modified_utf8_len).sum())
+                        .map_err(|err| err.prepend(PathPart::MapKey(key.clone())))?;
+                    value
+                        .validate(opts)
+                        .map_err(|err| err.prepend(PathPart::MapKey(key.clone())))?;
+                }
+                Ok(())
+            }
+            NBTTag::List(v) => {
+                for (i, value) in v.iter().enumerate() {
+                    if value.tag_type() != v[0].tag_type() {
+                        return Err(ErrorPath::new(ValidateError::MixedList {
+                            expected: v[0].tag_type(),
+                            found: value.tag_type(),
+                        })
+                        .prepend(PathPart::Element(i)));
+                    }
+                    value
+                        .validate(opts)
+                        .map_err(|err| err.prepend(PathPart::Element(i)))?;
+                }
+                Ok(())
+            }
+            NBTTag::String(tag::String::Utf8(v)) => {
+                check_string_len(v.chars().map(modified_utf8_len).sum())
+            }
+            NBTTag::String(tag::String::Bytes(v)) => check_string_len(v.len()),
+            NBTTag::Float(v) if opts.reject_non_finite && !v.is_finite() => {
+                Err(ErrorPath::new(ValidateError::NonFiniteFloat))
+            }
+            NBTTag::Double(v) if opts.reject_non_finite && !v.is_finite() => {
+                Err(ErrorPath::new(ValidateError::NonFiniteFloat))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Validates the NBT tag using the provided options and only then writes it into the buffer
+    /// using the specified [Writer] encoding.
+    ///
+    /// The data is encoded in memory before anything is written, so nothing at all is written to
+    /// the buffer if the data is invalid or fails to encode. Validation failures are returned as a
+    /// [WriteError::Invalid] error.
+    pub fn write_atomic(
+        &self,
+        buf: &mut impl Write,
+        w: &impl Writer,
+        opts: &ValidateOpts,
+    ) -> encode::Res {
+        self.validate(opts)
+            .map_err(|err| ErrorPath::new_with_path(WriteError::Invalid(err.inner), err.path))?;
+
+        let mut encoded = Vec::new();
+        self.write(&mut encoded, w)?;
+        buf.write_all(&encoded)
+            .map_err(|x| ErrorPath::new(x.into()))
+    }
+}
+
+/// Checks that an encoded string length fits in the length prefix of a string.
+fn check_string_len(len: usize) -> Res {
+    if len > i16::MAX as usize {
+        return Err(ErrorPath::new(ValidateError::StringTooLong(len)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::BigEndian;
+    use crate::err::{PathPart, ValidateError, WriteError};
+    use crate::tag;
+    use crate::validate::ValidateOpts;
+    use crate::NBTTag;
+
+    #[test]
+    fn test_empty_keys() {
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_list("a", vec![tag::Compound::builder().with_int("", 1).build()])
+                .build(),
+        );
+        assert!(nbt.validate(&ValidateOpts::default()).is_ok());
+
+        let opts = ValidateOpts {
+            reject_empty_keys: true,
+            ..Default::default()
+        };
+        let err = nbt.validate(&opts).unwrap_err();
+        assert_eq!(err.inner, ValidateError::EmptyKey);
+        assert_eq!(
+            Vec::from(err.path.0),
+            vec![
+                PathPart::MapKey("a".to_string()),
+                PathPart::Element(0),
+                PathPart::MapKey("".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let nbt = NBTTag::Compound(
+            tag::Compound::builder()
+                .with_string("name", "a")
+                .with_list(
+                    "list",
+                    vec![NBTTag::Int(tag::Int(1)), NBTTag::Long(tag::Long(2))],
+                )
+                .build(),
+        );
+        let mut buf = vec![];
+        let err = nbt
+            .write_atomic(&mut buf, &BigEndian, &Default::default())
+            .unwrap_err();
+        assert!(matches!(
+            err.inner,
+            WriteError::Invalid(ValidateError::MixedList { .. })
+        ));
+        assert_eq!(
+            Vec::from(err.path.0),
+            vec![PathPart::MapKey("list".to_string()), PathPart::Element(1)]
+        );
+        assert!(buf.is_empty());
+
+        let nbt = NBTTag::String(tag::String::from("a".repeat(i16::MAX as usize + 1)));
+        let err = nbt.validate(&Default::default()).unwrap_err();
+        assert_eq!(
+            err.inner,
+            ValidateError::StringTooLong(i16::MAX as usize + 1)
+        );
+
+        let nbt = NBTTag::Float(tag::Float(f32::NAN));
+        assert!(nbt.validate(&Default::default()).is_ok());
+        let opts = ValidateOpts {
+            reject_non_finite: true,
+            ..Default::default()
+        };
+        let err = nbt.write_atomic(&mut buf, &BigEndian, &opts).unwrap_err();
+        assert!(matches!(
+            err.inner,
+            WriteError::Invalid(ValidateError::NonFiniteFloat)
+        ));
+
+        let nbt = NBTTag::Float(tag::Float(1.0));
+        nbt.write_atomic(&mut buf, &BigEndian, &opts).unwrap();
+        let mut expected = vec![];
+        nbt.write(&mut expected, &BigEndian).unwrap();
+        assert_eq!(buf, expected);
+    }
+}
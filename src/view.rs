@@ -19,9 +19,9 @@
 //! ```
 
 use std::borrow::Cow;
-use std::collections::hash_map;
 use std::slice;
 
+use indexmap::map;
 use thiserror::Error;
 
 use crate::{tag, NBTTag, NBTTagType};
@@ -345,6 +345,36 @@ impl<'a> View<'a> {
         }
     }
 
+    /// Returns the value of the tag as an i64 if the underlying tag is any integer tag, in the same
+    /// way as [Self::any_int].
+    pub fn to_owned_i64(&self) -> Result<i64, ViewError> {
+        self.any_int()
+    }
+
+    /// Returns a copy of the string value of the tag if the underlying tag is a [tag::String], so
+    /// that it is no longer tied to the lifetime of the source tag.
+    pub fn to_owned_string(&self) -> Result<String, ViewError> {
+        self.string().map(str::to_owned)
+    }
+
+    /// Returns a copy of the underlying tag, so that it is no longer tied to the lifetime of the
+    /// source tag.
+    ///
+    /// ```
+    /// # use zuri_nbt::{nbt, NBTTag};
+    /// let owned = {
+    ///     let nbt = nbt!({"items": [{"id": 5i16}]});
+    ///     nbt.view().at("items").at(0).to_owned_tag()
+    /// };
+    /// assert_eq!(owned, Ok(nbt!({"id": 5i16})));
+    /// ```
+    pub fn to_owned_tag(&self) -> Result<NBTTag, ViewError> {
+        match &self.tag {
+            InnerView::Ok(v) => Ok(v.as_ref().clone()),
+            InnerView::NotFound(v) => Err(ViewError::MissingTag(*v)),
+        }
+    }
+
     /// Iterates over all entries in the view's container, or the underlying tag itself if it is not
     /// a container.
     ///
@@ -458,7 +488,7 @@ impl<'a> Iterator for ViewIterator<'a> {
 
 #[derive(Debug, Clone)]
 enum InnerViewIterator<'a> {
-    Compound(hash_map::Iter<'a, String, NBTTag>),
+    Compound(map::Iter<'a, String, NBTTag>),
     List(slice::Iter<'a, NBTTag>),
     ByteArray(slice::Iter<'a, i8>),
     IntArray(slice::Iter<'a, i32>),